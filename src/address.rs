@@ -0,0 +1,80 @@
+//! Account addresses.
+
+use std::fmt::{Display, Formatter, Error};
+use std::str::FromStr;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::hex_string;
+
+use hex_string::HexError;
+
+/// Length of an address in bytes.
+pub const ADDRESS_LENGTH: usize = 20;
+
+/// Error parsing an address from a hex string.
+#[derive(Debug)]
+pub enum AddressError {
+    InvalidHex(hex::FromHexError),
+    InvalidLength(usize),
+}
+
+impl From<HexError> for AddressError {
+    fn from(error: HexError) -> AddressError {
+        match error {
+            HexError::InvalidHex(error) => AddressError::InvalidHex(error),
+            HexError::InvalidLength(length, _) => AddressError::InvalidLength(length),
+        }
+    }
+}
+
+impl Display for AddressError {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        match self {
+            AddressError::InvalidHex(error) => write!(fmt, "Invalid hex string: {}", error),
+            AddressError::InvalidLength(length) => write!(fmt, "Invalid address length: {} bytes instead of {}",
+                length, ADDRESS_LENGTH),
+        }
+    }
+}
+
+/// Account address, written as 40 hex digits.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Address([u8; ADDRESS_LENGTH]);
+
+impl Address {
+    /// Wraps raw address bytes.
+    pub const fn new(bytes: [u8; ADDRESS_LENGTH]) -> Address {
+        Address(bytes)
+    }
+    
+    /// Returns the raw address bytes.
+    pub fn as_bytes(&self) -> &[u8; ADDRESS_LENGTH] {
+        &self.0
+    }
+}
+
+impl Display for Address {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        write!(fmt, "{}", hex::encode(self.0))
+    }
+}
+
+impl FromStr for Address {
+    type Err = AddressError;
+    
+    fn from_str(string: &str) -> Result<Address, AddressError> {
+        Ok(Address(hex_string::decode_hex(string)?))
+    }
+}
+
+impl Serialize for Address {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        hex_string::serialize_str(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Address {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Address, D::Error> {
+        hex_string::deserialize_str(deserializer)
+    }
+}
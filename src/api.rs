@@ -0,0 +1,339 @@
+//! Request and response bodies of the node's HTTP API, shared by the server and the client.
+
+use std::fmt::{Display, Formatter, Error};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{hash, address, reward, transaction, chain};
+
+use hash::Hash;
+use address::Address;
+use transaction::Transaction;
+use chain::ValidationError;
+
+/// Body holding either a single item or a batch of items.
+///
+/// A batch is tried first: a derived struct also deserializes from a sequence, which would read a batch as one item.
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum OneOrMany<T> {
+    Many(Vec<T>),
+    One(T),
+}
+
+impl<T> OneOrMany<T> {
+    /// Tells whether the body is a batch.
+    pub fn is_batch(&self) -> bool {
+        matches!(self, OneOrMany::Many(_))
+    }
+    
+    /// Returns the items, a single item becoming a batch of one.
+    pub fn into_vec(self) -> Vec<T> {
+        match self {
+            OneOrMany::One(item) => vec![item],
+            OneOrMany::Many(items) => items,
+        }
+    }
+}
+
+/// Body of every error response.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: ErrorDetails,
+}
+
+/// Machine-readable error code, human-readable message and optional structured details of an error.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorDetails {
+    pub code: String,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+}
+
+impl ErrorResponse {
+    /// Creates an error response.
+    pub fn new(code: &str, message: &str, details: Option<Value>) -> ErrorResponse {
+        ErrorResponse {
+            error: ErrorDetails {
+                code: String::from(code),
+                message: String::from(message),
+                details,
+            },
+        }
+    }
+}
+
+/// Invalid field of a request body; `item` is the position in a batch.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FieldError {
+    pub item: Option<usize>,
+    pub field: String,
+    pub message: String,
+}
+
+impl Display for FieldError {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        match self.item {
+            Some(item) => write!(fmt, "Item {}: {} {}", item, self.field, self.message),
+            None => write!(fmt, "{} {}", self.field, self.message),
+        }
+    }
+}
+
+impl FieldError {
+    /// Creates an error for `field`.
+    pub fn new(field: &str, message: &str) -> FieldError {
+        FieldError {
+            item: None,
+            field: String::from(field),
+            message: String::from(message),
+        }
+    }
+    
+    /// Attributes the error to the batch item at position `item`.
+    pub fn for_item(mut self, item: usize) -> FieldError {
+        self.item = Some(item);
+        self
+    }
+}
+
+/// Body of a node registration.
+#[derive(Deserialize, Serialize)]
+pub struct NodeRequest {
+    url: Option<Value>,
+}
+
+impl NodeRequest {
+    /// Creates a registration of the node at `url`.
+    pub fn new(url: &str) -> NodeRequest {
+        NodeRequest {
+            url: Some(Value::from(url)),
+        }
+    }
+    
+    /// Returns the url, or an error for every invalid field.
+    pub fn validate(&self) -> Result<String, Vec<FieldError>> {
+        match &self.url {
+            Some(Value::String(url)) if !url.trim().is_empty() => Ok(url.clone()),
+            Some(Value::String(_)) => Err(vec![FieldError::new("url", "must not be empty")]),
+            Some(_) => Err(vec![FieldError::new("url", "must be a string")]),
+            None => Err(vec![FieldError::new("url", "is required")]),
+        }
+    }
+}
+
+/// Query of the chain stream.
+#[derive(Deserialize, Serialize)]
+pub struct ChainStreamQuery {
+    /// Index of the first streamed block, counting from one; the stream starts with the genesis block by default.
+    pub from: Option<usize>,
+}
+
+/// Body of a new transaction.
+#[derive(Deserialize, Serialize)]
+pub struct TransactionRequest {
+    sender: Option<Value>,
+    recipient: Option<Value>,
+    amount: Option<Value>,
+}
+
+impl TransactionRequest {
+    /// Creates a request adding `transaction`.
+    pub fn new(transaction: &Transaction) -> TransactionRequest {
+        TransactionRequest {
+            sender: Some(Value::from(transaction.sender())),
+            recipient: Some(Value::from(transaction.recipient())),
+            amount: Some(Value::from(transaction.amount())),
+        }
+    }
+    
+    /// Returns the transaction, or an error for every invalid field.
+    pub fn validate(&self) -> Result<Transaction, Vec<FieldError>> {
+        let mut errors = Vec::new();
+        let sender = TransactionRequest::string_field("sender", &self.sender, &mut errors);
+        let recipient = TransactionRequest::string_field("recipient", &self.recipient, &mut errors);
+        
+        if sender.as_deref() == Some(reward::SENDER_FOR_MINING) {
+            errors.push(FieldError::new("sender", "is reserved for mining rewards"));
+        }
+        
+        let amount = match &self.amount {
+            Some(Value::Number(amount)) => match amount.as_i64() {
+                Some(amount) if amount > 0 => Some(amount),
+                Some(_) => {
+                    errors.push(FieldError::new("amount", "must be positive"));
+                    None
+                },
+                None => {
+                    errors.push(FieldError::new("amount", "must be an integer"));
+                    None
+                },
+            },
+            Some(_) => {
+                errors.push(FieldError::new("amount", "must be an integer"));
+                None
+            },
+            None => {
+                errors.push(FieldError::new("amount", "is required"));
+                None
+            },
+        };
+        
+        match (sender, recipient, amount) {
+            (Some(sender), Some(recipient), Some(amount)) if errors.is_empty() =>
+                Ok(Transaction::new(sender.as_str(), recipient.as_str(), amount)),
+            _ => Err(errors),
+        }
+    }
+    
+    fn string_field(field: &str, value: &Option<Value>, errors: &mut Vec<FieldError>) -> Option<String> {
+        match value {
+            Some(Value::String(string)) if !string.trim().is_empty() => Some(string.clone()),
+            Some(Value::String(_)) => {
+                errors.push(FieldError::new(field, "must not be empty"));
+                None
+            },
+            Some(_) => {
+                errors.push(FieldError::new(field, "must be a string"));
+                None
+            },
+            None => {
+                errors.push(FieldError::new(field, "is required"));
+                None
+            },
+        }
+    }
+}
+
+/// Optional body of a mining request.
+#[derive(Deserialize, Serialize)]
+pub struct MineRequest {
+    payout_address: Option<Address>,
+}
+
+impl MineRequest {
+    /// Creates a request paying the reward to `payout_address` instead of the node's configured address.
+    pub fn new(payout_address: Option<Address>) -> MineRequest {
+        MineRequest {
+            payout_address,
+        }
+    }
+    
+    /// Returns the requested payout address.
+    pub fn payout_address(&self) -> Option<Address> {
+        self.payout_address
+    }
+}
+
+/// Result of validating a chain; `index` is the offending block.
+#[derive(Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub valid: bool,
+    pub index: Option<usize>,
+    pub error: Option<ValidationError>,
+    pub message: Option<String>,
+}
+
+impl ValidationReport {
+    /// Creates a report of a validation result.
+    pub fn new(result: Result<(), ValidationError>) -> ValidationReport {
+        match result {
+            Ok(()) => ValidationReport {
+                valid: true,
+                index: None,
+                error: None,
+                message: None,
+            },
+            Err(error) => ValidationReport {
+                valid: false,
+                index: error.index(),
+                message: Some(error.to_string()),
+                error: Some(error),
+            },
+        }
+    }
+}
+
+/// Liveness and readiness of a node.
+#[derive(Serialize, Deserialize)]
+pub struct HealthReport {
+    pub live: bool,
+    pub ready: bool,
+    pub state_owner_running: bool,
+}
+
+impl HealthReport {
+    /// Creates a report; the node is ready while the owner of its state is running.
+    pub fn new(state_owner_running: bool) -> HealthReport {
+        HealthReport {
+            live: true,
+            ready: state_owner_running,
+            state_owner_running,
+        }
+    }
+    
+    /// Tells whether the node can serve requests.
+    pub fn is_ready(&self) -> bool {
+        self.ready
+    }
+}
+
+/// Whether a node can mine without a requested payout address and whether it is mining right now.
+#[derive(Serialize, Deserialize)]
+pub struct MiningStatus {
+    pub enabled: bool,
+    pub active: bool,
+    pub payout_address: Option<Address>,
+}
+
+/// Overview of a node.
+#[derive(Serialize, Deserialize)]
+pub struct NodeInfo {
+    pub version: String,
+    pub network_id: String,
+    pub net_location: String,
+    pub height: usize,
+    pub tip_hash: Hash,
+    pub mempool_size: usize,
+    pub peer_count: usize,
+    pub mining: MiningStatus,
+    pub uptime_secs: u64,
+}
+
+/// Outcome of conflict resolution.
+#[derive(Serialize, Deserialize)]
+pub enum ResolvingResult {
+    Done,
+    NoColflicts,
+}
+
+impl Display for ResolvingResult {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        match self {
+            ResolvingResult::Done => write!(fmt, "Conflicts resolved"),
+            ResolvingResult::NoColflicts => write!(fmt, "No conflicts found"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn one_or_many_reads_a_single_item() {
+        let body = serde_json::from_str::<OneOrMany<NodeRequest>>(r#"{"url": "http://node:8080"}"#).unwrap();
+        
+        assert!(!body.is_batch());
+        assert_eq!(body.into_vec()[0].validate().unwrap(), "http://node:8080");
+    }
+    
+    #[test]
+    fn one_or_many_reads_a_batch() {
+        let body = serde_json::from_str::<OneOrMany<NodeRequest>>(r#"[{"url": "http://node:8080"}]"#).unwrap();
+        
+        assert!(body.is_batch());
+        assert_eq!(body.into_vec()[0].validate().unwrap(), "http://node:8080");
+    }
+}
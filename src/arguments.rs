@@ -0,0 +1,90 @@
+use std::path::PathBuf;
+use clap::Parser;
+
+use crate::logging;
+
+use logging::LogFormat;
+
+#[derive(Parser)]
+#[command(version, about = "Simple blockchain node")]
+pub struct Arguments {
+    /// Settings file; defaults to "settings.json" in the working directory when it exists
+    #[arg(long, env = "BLOCKCHAIN_CONFIG")]
+    config: Option<PathBuf>,
+    /// Socket address to listen on, e.g. "127.0.0.1:8080"
+    #[arg(long, env = "BLOCKCHAIN_BIND")]
+    bind: Option<String>,
+    /// Address other nodes reach this node at, e.g. "http://node.example.com:8080"
+    #[arg(long, env = "BLOCKCHAIN_PUBLIC_ADDRESS")]
+    public_address: Option<String>,
+    /// Directory the node keeps its chain in
+    #[arg(long, env = "BLOCKCHAIN_DATA_DIR")]
+    data_dir: Option<PathBuf>,
+    /// Peer node url to register on startup; may be repeated
+    #[arg(long = "peer", env = "BLOCKCHAIN_PEERS", value_delimiter = ',')]
+    peers: Vec<String>,
+    /// Mine blocks continuously
+    #[arg(long, env = "BLOCKCHAIN_MINE")]
+    mine: bool,
+    /// Address receiving mining rewards
+    #[arg(long, env = "BLOCKCHAIN_PAYOUT_ADDRESS")]
+    payout_address: Option<String>,
+    /// Network identifier reported by the node
+    #[arg(long, env = "BLOCKCHAIN_NETWORK_ID")]
+    network_id: Option<String>,
+    /// Log filter, e.g. "info,blockchain::blockchain=debug"
+    #[arg(long, env = "BLOCKCHAIN_LOG")]
+    log_filter: Option<String>,
+    /// Log output format
+    #[arg(long, env = "BLOCKCHAIN_LOG_FORMAT", value_enum)]
+    log_format: Option<LogFormat>,
+    /// Print the effective configuration and exit
+    #[arg(long)]
+    print_config: bool,
+}
+
+impl Arguments {
+    pub fn config(&self) -> Option<&PathBuf> {
+        self.config.as_ref()
+    }
+    
+    pub fn bind(&self) -> Option<&str> {
+        self.bind.as_deref()
+    }
+    
+    pub fn public_address(&self) -> Option<&str> {
+        self.public_address.as_deref()
+    }
+    
+    pub fn data_dir(&self) -> Option<&PathBuf> {
+        self.data_dir.as_ref()
+    }
+    
+    pub fn peers(&self) -> &[String] {
+        self.peers.as_slice()
+    }
+    
+    pub fn mine(&self) -> bool {
+        self.mine
+    }
+    
+    pub fn payout_address(&self) -> Option<&str> {
+        self.payout_address.as_deref()
+    }
+    
+    pub fn network_id(&self) -> Option<&str> {
+        self.network_id.as_deref()
+    }
+    
+    pub fn log_filter(&self) -> Option<&str> {
+        self.log_filter.as_deref()
+    }
+    
+    pub fn log_format(&self) -> Option<LogFormat> {
+        self.log_format
+    }
+    
+    pub fn print_config(&self) -> bool {
+        self.print_config
+    }
+}
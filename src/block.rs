@@ -1,68 +1,129 @@
-use sha256::digest;
-use serde::{Deserialize, Serialize};
+//! Blocks.
 
-use crate::{time, transactions};
+use std::sync::OnceLock;
+use serde::{Deserialize, Deserializer, Serialize, de};
 
+use crate::{canonical, hash, time, transactions};
+
+use hash::Hash;
 use time::Time;
 use transactions::Transactions;
 
+/// Index of the genesis block; indexes start at one.
+pub const INITIAL_INDEX: usize = 1;
+/// Proof of the genesis block.
+pub const INITIAL_PROOF: i64 = 100;
+/// Previous hash of the genesis block.
+pub const INITIAL_PREVIOUS_HASH: Hash = Hash::new([0; hash::HASH_LENGTH]);
+
+/// Block of the chain.
+///
+/// Blocks without a `version` field are treated as legacy version 0 blocks; blocks of a version this build doesn't
+/// know are rejected when deserialized, since they can't be hashed.
 #[derive(Clone, Deserialize, Serialize)]
 pub struct Block {
+    #[serde(default, deserialize_with = "deserialize_version")]
+    version: u8,
     index: usize,
     date_time: Time,
     transactions: Transactions,
     proof: i64,
-    previous_hash: String,
+    previous_hash: Hash,
+    #[serde(skip)]
+    hash: OnceLock<Hash>,
 }
 
 impl Block {
-    pub fn new(index: usize, date_time: Time, transactions: &Transactions, proof: i64, previous_hash: &str) -> Block {
+    /// Creates a block in the current format version.
+    pub fn new(index: usize, date_time: Time, transactions: &Transactions, proof: i64, previous_hash: Hash) -> Block {
         Block {
+            version: canonical::CURRENT_VERSION,
             index,
             date_time,
             transactions: transactions.clone(),
             proof,
-            previous_hash: String::from(previous_hash),
+            previous_hash,
+            hash: OnceLock::new(),
         }
     }
     
+    /// Creates the genesis block dated `date_time`.
+    pub fn genesis(date_time: Time) -> Block {
+        Block::new(INITIAL_INDEX, date_time, &Transactions::new(), INITIAL_PROOF, INITIAL_PREVIOUS_HASH)
+    }
+    
+    /// Returns the format version the block is hashed with.
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+    
+    /// Returns the block's position in the chain, starting at one.
     pub fn index(&self) -> usize {
         self.index
     }
     
-    #[allow(dead_code)]
+    /// Returns the time the block was mined.
     pub fn date_time(&self) -> Time {
         self.date_time
     }
     
-    #[allow(dead_code)]
+    /// Returns the block's transactions; a mined block ends with its reward.
     pub fn transactions(&self) -> &Transactions {
         &self.transactions
     }
     
+    /// Returns the proof of work.
     pub fn proof(&self) -> i64 {
         self.proof
     }
     
-    pub fn previous_hash(&self) -> &str {
-        self.previous_hash.as_str()
+    /// Returns the hash of the previous block.
+    pub fn previous_hash(&self) -> Hash {
+        self.previous_hash
     }
     
+    /// Tells whether the block claims to be the genesis block.
     pub fn is_first(&self) -> bool {
-        self.index == 1
+        self.index == INITIAL_INDEX
     }
     
-    pub fn hash(&self) -> String {
-        let mut string = format!("{}|{}|", self.index, self.date_time);
-        
-        for transaction in self.transactions.iter() {
-            string.push_str(transaction.hash().as_str());
-            string.push_str("|");
-        }
+    /// Returns the block's hash, computed once and cached.
+    pub fn hash(&self) -> Hash {
+        *self.hash.get_or_init(|| match self.version {
+            canonical::LEGACY_VERSION => Hash::digest(canonical::encode_block_legacy(self).as_bytes()),
+            canonical::CURRENT_VERSION => Hash::digest(&canonical::encode_block(self)),
+            version => unreachable!("Block version {} must be rejected when deserialized", version),
+        })
+    }
+}
+
+fn deserialize_version<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+    match u8::deserialize(deserializer)? {
+        version @ (canonical::LEGACY_VERSION | canonical::CURRENT_VERSION) => Ok(version),
+        version => Err(de::Error::custom(format!("unsupported block version {}", version))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures;
+    
+    use super::*;
+    
+    fn block_json(version: Option<u8>) -> serde_json::Value {
+        fixtures::patched_json(&Block::genesis(Time::from_unix_millis(0)), "version", version.map(Into::into))
+    }
+    
+    #[test]
+    fn missing_version_is_legacy() {
+        let block = serde_json::from_value::<Block>(block_json(None)).unwrap();
         
-        string.push_str(self.proof.to_string().as_str());
-        string.push_str("|");
-        string.push_str(self.previous_hash());
-        digest(string)
+        assert_eq!(block.version(), canonical::LEGACY_VERSION);
+    }
+    
+    #[test]
+    fn unknown_version_is_rejected() {
+        assert!(serde_json::from_value::<Block>(block_json(Some(canonical::CURRENT_VERSION))).is_ok());
+        assert!(serde_json::from_value::<Block>(block_json(Some(canonical::CURRENT_VERSION + 1))).is_err());
     }
 }
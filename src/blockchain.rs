@@ -1,125 +1,232 @@
 use std::fmt::{Display, Formatter, Error};
-use serde::Serialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{info, warn, info_span, Instrument};
 
-use crate::{time, net_location, proof, node, nodes, transaction, transactions, block, chain, requests};
+use simple_blockchain::{time, clock, address, net_location, proof, reward, rules, node, transaction, transactions, block,
+    chain, api};
+use crate::{metrics, nodes, requests};
 
 use time::Time;
+use clock::Clock;
+use metrics::Metrics;
+use address::Address;
+use net_location::LocalAddresses;
 use node::Node;
 use nodes::{Nodes, RegisterError};
-use transaction::Transaction;
+use transaction::{Transaction, TransactionError};
 use transactions::Transactions;
+use reward::Supply;
+use rules::ValidationRules;
 use block::Block;
 use chain::{Chain, ChainError};
+use api::ResolvingResult;
 use requests::RequestError;
 
-#[derive(Serialize)]
-pub enum ResolvingResult {
-    Done,
-    NoColflicts,
+#[derive(Debug)]
+pub enum MiningError {
+    NoPayoutAddress,
 }
 
-impl Display for ResolvingResult {
+impl Display for MiningError {
     fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
         match self {
-            ResolvingResult::Done => write!(fmt, "Conflicts resolved"),
-            ResolvingResult::NoColflicts => write!(fmt, "No conflicts found"),
+            MiningError::NoPayoutAddress => write!(fmt, "Payout address is neither configured nor requested"),
         }
     }
 }
 
-const INITIAL_INDEX: usize = 1;
-const INITIAL_PROOF: i64 = 100;
-const INITIAL_PREVIOUS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
-const SENDER_FOR_MINING: &str = "Blockchain";
-const PAYMENT_FOR_MINING: i64 = 1;
-
+#[derive(Clone)]
 pub struct Blockchain {
-    net_location: String,
-    nodes: Nodes,
+    clock: Arc<dyn Clock>,
+    metrics: Arc<Metrics>,
+    started_at: Time,
+    network_id: String,
+    local_addresses: LocalAddresses,
+    payout_address: Option<Address>,
+    rules: ValidationRules,
+    nodes: Arc<Nodes>,
     chain: Chain,
-    current_transactions: Transactions,
+    current_transactions: Arc<Transactions>,
 }
 
 impl Blockchain {
-    pub fn new(net_location: &str) -> Blockchain {
-        let block = Block::new(INITIAL_INDEX, Time::now(), &Transactions::new(), INITIAL_PROOF, INITIAL_PREVIOUS_HASH);
+    pub fn new(network_id: &str, local_addresses: LocalAddresses, payout_address: Option<Address>,
+        rules: ValidationRules, clock: Arc<dyn Clock>, metrics: Arc<Metrics>) -> Blockchain {
+        let started_at = clock.now();
+        let block = Block::genesis(started_at);
         
         Blockchain {
-            net_location: String::from(net_location),
-            nodes: Nodes::new(),
+            clock,
+            metrics,
+            started_at,
+            network_id: String::from(network_id),
+            local_addresses,
+            payout_address,
+            rules,
+            nodes: Arc::new(Nodes::new()),
             chain: Chain::new(&block),
-            current_transactions: Transactions::new(),
+            current_transactions: Arc::new(Transactions::new()),
+        }
+    }
+    
+    pub fn network_id(&self) -> &str {
+        self.network_id.as_str()
+    }
+    
+    pub fn uptime(&self) -> Duration {
+        match self.clock.now().system_time().duration_since(self.started_at.system_time()) {
+            Ok(uptime) => uptime,
+            Err(_) => Duration::ZERO,
         }
     }
     
     pub fn net_location(&self) -> &str {
-        self.net_location.as_str()
+        self.local_addresses.public_net_location()
+    }
+    
+    pub fn local_addresses(&self) -> &LocalAddresses {
+        &self.local_addresses
+    }
+    
+    pub fn clock(&self) -> &dyn Clock {
+        self.clock.as_ref()
+    }
+    
+    pub fn payout_address(&self) -> Option<Address> {
+        self.payout_address
+    }
+    
+    pub fn metrics(&self) -> &Metrics {
+        self.metrics.as_ref()
+    }
+    
+    pub fn rules(&self) -> &ValidationRules {
+        &self.rules
+    }
+    
+    pub fn supply(&self) -> Supply {
+        self.rules.emission_schedule().supply(self.chain.num_of_blocks())
     }
     
     pub fn nodes(&self) -> &Nodes {
         &self.nodes
     }
     
-    pub fn register_node(&mut self, url: &str) -> Result<&Node, RegisterError> {
-        println!("Blockchain: Node registration: Url =\"{}\"", url);
-        
-        let nel_location = match net_location::net_location_by_url(url) {
-            Ok(nel_location) => nel_location,
+    pub fn check_node(&self, url: &str) -> Result<Node, RegisterError> {
+        let node = match Node::from_url(url) {
+            Ok(node) => node,
             Err(error) => {
                 return Err(RegisterError::ParseError(error));
             },
         };
         
-        if nel_location == self.net_location {
+        if self.local_addresses.contains(node.net_location()) {
             return Err(RegisterError::EqualsToTheCurrentNode);
         }
         
-        let node = self.nodes.register(url);
+        if self.nodes.contains(node.net_location()) {
+            return Err(RegisterError::HasBeenRegistered);
+        }
+        
+        Ok(node)
+    }
+    
+    pub fn register_nodes(&mut self, urls: &[String]) -> Result<Vec<Node>, RegisterError> {
+        let mut nodes = Vec::new();
+        
+        for url in urls {
+            nodes.push(self.check_node(url.as_str())?);
+        }
         
-        match node {
-            Ok(node) => Ok(node),
+        for node in nodes.iter() {
+            info!(url = node.url(), "Registering node");
+            
+            Arc::make_mut(&mut self.nodes).register(node.clone())?;
+        }
+        
+        Ok(nodes)
+    }
+    
+    pub fn remove_node(&mut self, url: &str) -> Result<Node, RegisterError> {
+        let node = match Node::from_url(url) {
+            Ok(node) => node,
             Err(error) => {
-                return Err(error);
+                return Err(RegisterError::ParseError(error));
             },
-        }
+        };
+        
+        let node = Arc::make_mut(&mut self.nodes).remove(node.net_location())?;
+        info!(url = node.url(), "Removing node");
+        
+        Ok(node)
     }
     
-    pub async fn resolve_conflicts(&mut self) -> Result<ResolvingResult, RequestError> {
-        println!("Blockchain: Resolving conflicts");
+    pub async fn find_longest_chain(&self) -> Result<Option<Chain>, RequestError> {
+        info!("Resolving conflicts");
         
+        let started_at = Instant::now();
         let mut max_num_of_blocks = self.chain().num_of_blocks();
         let mut new_chain: Option<Chain> = None;
         
         for node in self.nodes.iter() {
-            let chain = requests::load_chain(node.net_location()).await?;
+            let chain = requests::load_longer_chain(node.url(), self.chain.clone(), &self.rules, self.clock.now())
+                .instrument(info_span!("peer", peer = node.net_location()))
+                .await;
+            
+            let chain = match chain {
+                Ok(Some(chain)) => chain,
+                Ok(None) => continue,
+                Err(RequestError::InvalidChain(url, error)) => {
+                    warn!(peer = node.net_location(), url = url.as_str(), %error, "Chain rejected");
+                    self.metrics.observe_peer_failure(node.net_location());
+                    continue;
+                },
+                Err(error) => {
+                    warn!(peer = node.net_location(), %error, "Chain download failed");
+                    self.metrics.observe_peer_failure(node.net_location());
+                    self.metrics.observe_resolve_conflicts(started_at.elapsed());
+                    return Err(error);
+                },
+            };
+            
             let num_of_blocks = chain.num_of_blocks();
             
-            if num_of_blocks > max_num_of_blocks
-                && chain.is_valid() {
+            if num_of_blocks > max_num_of_blocks {
                 max_num_of_blocks = num_of_blocks;
                 new_chain = Some(chain);
             }
         }
         
-        match new_chain {
-            Some(chain) => {
-                self.set_chain(chain);
-                Ok(ResolvingResult::Done)
-            },
-            None => {
-                Ok(ResolvingResult::NoColflicts)
-            },
-        }
+        self.metrics.observe_resolve_conflicts(started_at.elapsed());
+        Ok(new_chain)
     }
     
-    pub fn chain(&self) -> &Chain {
-        &self.chain
+    pub fn adopt_chain(&mut self, chain: Chain) -> ResolvingResult {
+        if chain.num_of_blocks() <= self.chain.num_of_blocks() {
+            return ResolvingResult::NoColflicts;
+        }
+        
+        let reorg_depth = self.chain.reorg_depth(&chain);
+        
+        if reorg_depth > 0 {
+            warn!(depth = reorg_depth, "Chain reorganized");
+            self.metrics.observe_reorg(reorg_depth);
+        }
+        
+        self.chain = chain;
+        ResolvingResult::Done
     }
     
-    pub fn set_chain(&mut self, chain: Chain) {
+    /// Replaces the chain with a validated chain saved by an earlier run.
+    pub fn restore_chain(&mut self, chain: Chain) {
         self.chain = chain;
     }
     
+    pub fn chain(&self) -> &Chain {
+        &self.chain
+    }
+    
     pub fn block(&self, index: usize) -> Result<&Block, ChainError> {
         self.chain.block(index)
     }
@@ -128,36 +235,118 @@ impl Blockchain {
         self.chain.last().expect("Blockchain must containt at least one block")
     }
     
-    pub fn mine(&mut self) -> &Block {
-        println!("Blockchain: Beginning of mining...");
+    pub fn mine(&mut self, payout_address: Option<Address>) -> Result<Block, MiningError> {
+        let payout_address = match payout_address.or(self.payout_address) {
+            Some(payout_address) => payout_address,
+            None => {
+                return Err(MiningError::NoPayoutAddress);
+            },
+        };
         
-        let last_block = self.last_block();
-        let proof = proof::proof_of_work(last_block.proof());
-        self.add_transaction_to_current_transactions(Transaction::new(SENDER_FOR_MINING, self.net_location.as_str(),
-            PAYMENT_FOR_MINING));
+        let _span = info_span!("mine", block_index = self.chain.num_of_blocks() + 1).entered();
+        info!(payout_address = %payout_address, "Mining started");
         
-        println!("Blockchain: Ending of mining");
+        let started_at = Instant::now();
+        let proof = proof::proof_of_work(self.last_block().proof());
+        self.metrics.observe_mining(proof as u64 + 1, started_at.elapsed());
+        let reward = self.rules.emission_schedule().reward(self.chain.num_of_blocks() + 1);
+        let mut transactions = Transactions::clone(&self.current_transactions);
+        transactions.push(Transaction::reward(payout_address.to_string().as_str(), reward));
         
-        self.add_block_to_chain(proof)
+        info!(proof, "Mining finished");
+        
+        Ok(self.add_block_to_chain(&transactions, proof).clone())
     }
     
-    fn add_block_to_chain(&mut self, proof: i64) -> &Block {
+    fn add_block_to_chain(&mut self, transactions: &Transactions, proof: i64) -> &Block {
         let index = self.chain.num_of_blocks() + 1;
-        let block = Block::new(index, Time::now(), &self.current_transactions, proof,
-            self.last_block().hash().as_str());
-        let block = self.chain.push(block);
-        self.current_transactions.clear();
-        block
+        // A clock behind the median time of the last blocks would date the block invalid.
+        let now = self.clock.now();
+        let date_time = self.chain.min_next_date_time(&self.rules).map_or(now, |min_date_time| now.max(min_date_time));
+        let block = Block::new(index, date_time, transactions, proof, self.last_block().hash());
+        self.current_transactions = Arc::new(Transactions::new());
+        self.chain.push(block)
     }
     
     pub fn current_transactions(&self) -> &Transactions {
         &self.current_transactions
     }
     
-    pub fn add_transaction_to_current_transactions(&mut self, transaction: Transaction) -> &Transaction {
-        println!("Blockchain: Creating new transaction: \"{}\" / \"{}\" / \"{}\"", transaction.sender(),
-            transaction.recipient(), transaction.amount());
+    pub fn add_transactions_to_current_transactions(&mut self, transactions: Vec<Transaction>) ->
+        Result<Vec<Transaction>, TransactionError> {
+        if transactions.iter().any(|transaction| transaction.is_reward()) {
+            return Err(TransactionError::ReservedSender);
+        }
+        
+        let current_transactions = Arc::make_mut(&mut self.current_transactions);
+        
+        for transaction in transactions.iter() {
+            info!(sender = transaction.sender(), recipient = transaction.recipient(), amount = transaction.amount(),
+                "Adding transaction");
+            
+            current_transactions.push(transaction.clone());
+        }
+        
+        Ok(transactions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use simple_blockchain::clock::MockClock;
+    
+    use super::*;
+    
+    const BLOCK_INTERVAL: Duration = Duration::from_secs(60);
+    
+    fn blockchain(clock: Arc<MockClock>) -> Blockchain {
+        let local_addresses = LocalAddresses::new("127.0.0.1:8080", None).unwrap();
+        
+        Blockchain::new("test", local_addresses, Some(Address::new([1; 20])), ValidationRules::default(), clock,
+            Arc::new(Metrics::new()))
+    }
+    
+    #[test]
+    fn mining_with_mock_clock_is_reproducible() {
+        let clock = Arc::new(MockClock::new(Time::from_unix_millis(1_600_000_000_000)));
+        let mut blockchain = blockchain(clock.clone());
+        
+        for _ in 0..2 {
+            clock.advance(BLOCK_INTERVAL);
+            blockchain.mine(None).unwrap();
+        }
+        
+        let hashes: Vec<String> = blockchain.chain().iter().map(|block| block.hash().to_string()).collect();
+        
+        assert_eq!(hashes, [
+            "420586ae6f1933ca6e6f2dfa2f7095afa48827146d81c6f8309d6c51dca0ec2b",
+            "6a4b7a98018e2fdd23fb7602948d21a7a6a6551d04fb00a199e381afe8737ed5",
+            "251654a7a9f4f90e3c3db2411eab0630c51303dcf42308d5437caf1e81b70a5f",
+        ]);
+        assert!(blockchain.chain().validate(blockchain.rules(), clock.now()).is_ok());
+    }
+    
+    #[test]
+    fn stopped_clock_mines_valid_chain() {
+        let clock = Arc::new(MockClock::new(Time::from_unix_millis(1_600_000_000_000)));
+        let mut blockchain = blockchain(clock.clone());
+        
+        for _ in 0..3 {
+            blockchain.mine(None).unwrap();
+        }
+        
+        assert!(blockchain.chain().validate(blockchain.rules(), clock.now()).is_ok());
+    }
+    
+    #[test]
+    fn clock_going_backwards_mines_valid_chain() {
+        let clock = Arc::new(MockClock::new(Time::from_unix_millis(1_600_000_000_000)));
+        let mut blockchain = blockchain(clock.clone());
+        clock.advance(BLOCK_INTERVAL);
+        blockchain.mine(None).unwrap();
+        clock.set(Time::from_unix_millis(1_600_000_030_000));
+        blockchain.mine(None).unwrap();
         
-        self.current_transactions.push(transaction)
+        assert!(blockchain.chain().validate(blockchain.rules(), clock.now()).is_ok());
     }
 }
@@ -0,0 +1,164 @@
+//! Canonical byte encoding of blocks and transactions used for hashing.
+//!
+//! Integers are big-endian, strings are UTF-8 prefixed by their byte length as `u64`, hashes are their 32 raw
+//! bytes. The encoding starts with the format version so that a block is always hashed with the format it was
+//! mined with.
+//!
+//! Version 1:
+//!
+//! ```text
+//! transaction = sender: string | recipient: string | amount: i64
+//! block       = version: u8 | index: u64 | seconds since epoch: i64 | nanoseconds: u32
+//!               | number of transactions: u64 | transaction* | proof: i64 | previous hash: [u8; 32]
+//! ```
+//!
+//! A standalone transaction is encoded as `version: u8 | transaction`. A signature covers
+//!
+//! ```text
+//! signing message = "simple-blockchain signed transaction": string | network id: string | nonce: u64
+//!                   | transaction hash: [u8; 32]
+//! ```
+//!
+//! so that it is only valid on one network and for one nonce of the sender.
+//!
+//! Version 0 is the legacy `|`-separated text of the index, `Time`'s `Display` output, the transaction hashes,
+//! the proof and the previous hash. It is only kept so that blocks mined before version 1 still validate.
+
+use crate::{hash, block, transaction};
+
+use hash::Hash;
+use block::Block;
+use transaction::Transaction;
+
+/// Format version of blocks mined before canonical encoding.
+pub const LEGACY_VERSION: u8 = 0;
+/// Format version of newly mined blocks.
+pub const CURRENT_VERSION: u8 = 1;
+
+const SIGNING_DOMAIN: &str = "simple-blockchain signed transaction";
+
+/// Encodes a standalone transaction.
+pub fn encode_transaction(transaction: &Transaction) -> Vec<u8> {
+    let mut bytes = vec![CURRENT_VERSION];
+    write_transaction(&mut bytes, transaction);
+    bytes
+}
+
+/// Encodes the message a transaction is signed over.
+pub fn encode_signing_message(network_id: &str, nonce: u64, transaction: &Transaction) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    write_string(&mut bytes, SIGNING_DOMAIN);
+    write_string(&mut bytes, network_id);
+    bytes.extend_from_slice(&nonce.to_be_bytes());
+    write_hash(&mut bytes, transaction.hash());
+    bytes
+}
+
+/// Encodes a block in the version 1 format.
+pub fn encode_block(block: &Block) -> Vec<u8> {
+    let mut bytes = vec![block.version()];
+    let (seconds, nanoseconds) = block.date_time().unix_time();
+    
+    bytes.extend_from_slice(&(block.index() as u64).to_be_bytes());
+    bytes.extend_from_slice(&seconds.to_be_bytes());
+    bytes.extend_from_slice(&nanoseconds.to_be_bytes());
+    bytes.extend_from_slice(&(block.transactions().iter().count() as u64).to_be_bytes());
+    
+    for transaction in block.transactions().iter() {
+        write_transaction(&mut bytes, transaction);
+    }
+    
+    bytes.extend_from_slice(&block.proof().to_be_bytes());
+    write_hash(&mut bytes, block.previous_hash());
+    bytes
+}
+
+/// Encodes a block in the legacy version 0 text format.
+pub fn encode_block_legacy(block: &Block) -> String {
+    let mut string = format!("{}|{}|", block.index(), block.date_time());
+    
+    for transaction in block.transactions().iter() {
+        let transaction_string = format!("{}|{}|{}", transaction.sender(), transaction.recipient(),
+            transaction.amount());
+        string.push_str(sha256::digest(transaction_string).as_str());
+        string.push('|');
+    }
+    
+    string.push_str(block.proof().to_string().as_str());
+    string.push('|');
+    string.push_str(block.previous_hash().to_string().as_str());
+    string
+}
+
+fn write_transaction(bytes: &mut Vec<u8>, transaction: &Transaction) {
+    write_string(bytes, transaction.sender());
+    write_string(bytes, transaction.recipient());
+    bytes.extend_from_slice(&transaction.amount().to_be_bytes());
+}
+
+fn write_string(bytes: &mut Vec<u8>, string: &str) {
+    bytes.extend_from_slice(&(string.len() as u64).to_be_bytes());
+    bytes.extend_from_slice(string.as_bytes());
+}
+
+fn write_hash(bytes: &mut Vec<u8>, hash: Hash) {
+    bytes.extend_from_slice(hash.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures;
+    
+    use super::*;
+    
+    fn block() -> Block {
+        fixtures::block(Hash::new([0x11; 32]))
+    }
+    
+    fn legacy_block() -> Block {
+        fixtures::patched(&block(), "version", None)
+    }
+    
+    #[test]
+    fn transaction_encoding_is_stable() {
+        let transaction = Transaction::new("alice", "bob", 5);
+        
+        assert_eq!(hex::encode(encode_transaction(&transaction)), concat!("01",
+            "0000000000000005", "616c696365", "0000000000000003", "626f62", "0000000000000005"));
+        assert_eq!(transaction.hash().to_string(), "2b78807d3ddb5b497019f2c20192121908e7a27e6537937379473806a0340d60");
+    }
+    
+    #[test]
+    fn signing_message_encoding_is_stable() {
+        let transaction = Transaction::new("alice", "bob", 5);
+        
+        assert_eq!(hex::encode(encode_signing_message("main", 7, &transaction)), concat!(
+            "0000000000000024", "73696d706c652d626c6f636b636861696e207369676e6564207472616e73616374696f6e",
+            "0000000000000004", "6d61696e", "0000000000000007",
+            "2b78807d3ddb5b497019f2c20192121908e7a27e6537937379473806a0340d60"));
+    }
+    
+    #[test]
+    fn block_encoding_is_stable() {
+        let block = block();
+        
+        assert_eq!(hex::encode(encode_block(&block)), concat!("01",
+            "0000000000000002", "000000005f5e103c", "0754d4c0", "0000000000000002",
+            "0000000000000005", "616c696365", "0000000000000003", "626f62", "0000000000000005",
+            "000000000000000a", "426c6f636b636861696e", "0000000000000005", "6d696e6572", "0000000000000032",
+            "00000000000089dd", "1111111111111111111111111111111111111111111111111111111111111111"));
+        assert_eq!(block.hash().to_string(), "e3736e40ed2d306a97b4c5ca681c9b2e3aff8609f63204169b28350ffa6ed8e4");
+    }
+    
+    #[test]
+    fn legacy_block_encoding_is_stable() {
+        let block = legacy_block();
+        
+        assert_eq!(block.version(), LEGACY_VERSION);
+        assert_eq!(encode_block_legacy(&block), concat!("2|2020.09.13 12:27:40.123000000|",
+            "cd871629e3a51a3c9782326419727151f391d894bc39a42d55699e43acd878c6|",
+            "b120908ffec6c7e80d859d93a1e3fab589aca59605ced8200f5654b9d766802f|",
+            "35293|1111111111111111111111111111111111111111111111111111111111111111"));
+        assert_eq!(block.hash().to_string(), "90243df13c70544616b9a70f93fa3511631f177d7527ea203961119c1f19b171");
+    }
+}
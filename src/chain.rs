@@ -1,10 +1,20 @@
+//! Chains of blocks and their validation.
+
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use im::{HashMap, Vector};
 use std::fmt::{Display, Formatter, Error};
 use serde::{Deserialize, Serialize};
 
-use crate::{proof, block};
+use crate::{time, proof, rules, hash, canonical, block};
+
+use time::Time;
+use rules::ValidationRules;
 
+use hash::Hash;
 use block::Block;
 
+/// Error looking up a block.
 #[derive(Debug)]
 pub enum ChainError {
     InvalidIndex,
@@ -18,22 +28,114 @@ impl Display for ChainError {
     }
 }
 
+/// Reason a chain is invalid, carrying the index of the offending block.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub enum ValidationError {
+    EmptyChain,
+    InvalidGenesis(usize),
+    DuplicateGenesis(usize),
+    UnsupportedVersion(usize),
+    VersionDowngrade(usize),
+    IndexGap(usize),
+    InvalidTimestamp(usize),
+    FutureTimestamp(usize),
+    InvalidPreviousHash(usize),
+    InvalidProof(usize),
+    MissingReward(usize),
+    InvalidReward(usize),
+    InvalidTransaction(usize),
+}
+
+impl ValidationError {
+    /// Returns the index of the offending block, if any.
+    pub fn index(&self) -> Option<usize> {
+        match self {
+            ValidationError::EmptyChain => None,
+            ValidationError::InvalidGenesis(index)
+                | ValidationError::DuplicateGenesis(index)
+                | ValidationError::UnsupportedVersion(index)
+                | ValidationError::VersionDowngrade(index)
+                | ValidationError::IndexGap(index)
+                | ValidationError::InvalidTimestamp(index)
+                | ValidationError::FutureTimestamp(index)
+                | ValidationError::InvalidPreviousHash(index)
+                | ValidationError::InvalidProof(index)
+                | ValidationError::MissingReward(index)
+                | ValidationError::InvalidReward(index)
+                | ValidationError::InvalidTransaction(index) => Some(*index),
+        }
+    }
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        match self {
+            ValidationError::EmptyChain => write!(fmt, "Chain contains no blocks"),
+            ValidationError::InvalidGenesis(index) => write!(fmt, "Block {} is not a valid genesis block", index),
+            ValidationError::DuplicateGenesis(index) => write!(fmt, "Block {} is a second genesis block", index),
+            ValidationError::UnsupportedVersion(index) => write!(fmt, "Block {} has unsupported format version",
+                index),
+            ValidationError::VersionDowngrade(index) => write!(fmt,
+                "Block {} has a lower format version than the previous block", index),
+            ValidationError::IndexGap(index) => write!(fmt, "Block {} has index out of sequence", index),
+            ValidationError::InvalidTimestamp(index) => write!(fmt,
+                "Block {} is not dated after the median time of the previous blocks", index),
+            ValidationError::FutureTimestamp(index) => write!(fmt, "Block {} is dated too far in the future", index),
+            ValidationError::InvalidPreviousHash(index) => write!(fmt,
+                "Block {} doesn\'t reference the previous block\'s hash", index),
+            ValidationError::InvalidProof(index) => write!(fmt, "Block {} has invalid proof of work", index),
+            ValidationError::MissingReward(index) => write!(fmt, "Block {} doesn\'t end with a mining reward", index),
+            ValidationError::InvalidReward(index) => write!(fmt, "Block {} pays an unscheduled mining reward", index),
+            ValidationError::InvalidTransaction(index) => write!(fmt,
+                "Block {} contains a transaction from the reserved sender", index),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ChainBlocks {
+    chain: Vec<Block>,
+}
+
+/// Sequence of blocks starting with the genesis block, indexed by block hash.
+///
+/// Blocks and the index live in persistent structures, so cloning a chain and appending a block to the clone take
+/// logarithmic time and share everything else with the original.
+///
+/// Deserializing a chain neither validates nor hashes it; call [`Chain::validate`] to validate it. The index by
+/// hash of a deserialized chain is built on the first lookup.
 #[derive(Clone, Deserialize, Serialize)]
+#[serde(from = "ChainBlocks")]
 pub struct Chain {
-    chain: Vec<Block>,
+    chain: Vector<Arc<Block>>,
+    #[serde(skip)]
+    heights: OnceLock<HashMap<Hash, usize>>,
+}
+
+impl From<ChainBlocks> for Chain {
+    fn from(chain_blocks: ChainBlocks) -> Chain {
+        Chain {
+            chain: chain_blocks.chain.into_iter().map(Arc::new).collect(),
+            heights: OnceLock::new(),
+        }
+    }
 }
 
 impl Chain {
+    /// Creates a chain consisting of `block`.
     pub fn new(block: &Block) -> Chain {
         Chain {
-            chain: Vec::from([block.clone()]),
+            chain: Vector::unit(Arc::new(block.clone())),
+            heights: OnceLock::from(HashMap::unit(block.hash(), 1)),
         }
     }
     
+    /// Returns the number of blocks, which is also the index of the last block.
     pub fn num_of_blocks(&self) -> usize {
         self.chain.len()
     }
     
+    /// Returns the block with `index`, starting at one.
     pub fn block(&self, index: usize) -> Result<&Block, ChainError> {
         let index = match index.checked_sub(1) {
             Some(index) => index,
@@ -48,6 +150,12 @@ impl Chain {
         }
     }
     
+    /// Iterates over the blocks, starting with the genesis block.
+    pub fn iter(&self) -> impl Iterator<Item = &Block> {
+        self.chain.iter().map(|block| block.as_ref())
+    }
+    
+    /// Returns the last block.
     pub fn last(&self) -> Option<&Block> {
         match self.chain.last() {
             Some(block) => Some(block),
@@ -55,32 +163,536 @@ impl Chain {
         }
     }
     
+    /// Returns the index of the block with `hash`.
+    pub fn height(&self, hash: &Hash) -> Option<usize> {
+        self.heights().get(hash).copied()
+    }
+    
+    /// Returns how many of this chain's blocks are not part of `new_chain`.
+    /// Returns the earliest time a block appended to the chain may be dated, just after the median time of the last
+    /// blocks, or `None` if the chain is empty.
+    pub fn min_next_date_time(&self, rules: &ValidationRules) -> Option<Time> {
+        if self.chain.is_empty() {
+            return None;
+        }
+        
+        let median_time = self.median_time(self.chain.len(), rules);
+        Some(Time::new(median_time.system_time() + Duration::from_nanos(1)))
+    }
+    
+    pub fn reorg_depth(&self, new_chain: &Chain) -> usize {
+        let common_height = self.chain.iter()
+            .enumerate()
+            .rev()
+            .find(|(position, block)| new_chain.height(&block.hash()) == Some(position + 1))
+            .map_or(0, |(position, _)| position + 1);
+        
+        self.chain.len() - common_height
+    }
+    
+    /// Appends a block without validation and returns it.
     pub fn push(&mut self, block: Block) -> &Block {
-        self.chain.push(block);
-        self.chain.last().expect("Chain must contain at least one item")
+        if let Some(heights) = self.heights.get_mut() {
+            heights.insert(block.hash(), self.chain.len() + 1);
+        }
+        
+        self.chain.push_back(Arc::new(block));
+        self.last().expect("Chain must contain at least one item")
+    }
+    
+    /// Appends a block if it validly extends the chain; the first block of an empty chain must be a valid genesis
+    /// block.
+    pub fn push_valid(&mut self, block: Block, rules: &ValidationRules, now: Time) -> Result<&Block, ValidationError> {
+        match self.chain.len() {
+            0 => Chain::validate_genesis(&block, rules, now)?,
+            position => self.validate_successor(position, &block, rules, now)?,
+        }
+        
+        Ok(self.push(block))
     }
     
-    pub fn is_valid(&self) -> bool {
-        !self.chain.iter().any(|block| !self.block_is_valid(&block))
+    /// Validates the whole chain, starting with the genesis block.
+    pub fn validate(&self, rules: &ValidationRules, now: Time) -> Result<(), ValidationError> {
+        let first_block = match self.chain.front() {
+            Some(block) => block,
+            None => {
+                return Err(ValidationError::EmptyChain);
+            },
+        };
+        
+        Chain::validate_genesis(first_block, rules, now)?;
+        self.validate_suffix(block::INITIAL_INDEX + 1, rules, now)
     }
     
-    fn block_is_valid(&self, block: &Block) -> bool {
+    /// Validates the blocks from `index` on, assuming the blocks before it are valid.
+    pub fn validate_suffix(&self, index: usize, rules: &ValidationRules, now: Time) -> Result<(), ValidationError> {
+        let position = index.max(block::INITIAL_INDEX + 1) - 1;
+        
+        for position in position..self.chain.len() {
+            self.validate_successor(position, &self.chain[position], rules, now)?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Appends `blocks` if they validly extend the chain; otherwise leaves the chain unchanged. An empty chain is
+    /// validated from its genesis block on.
+    ///
+    /// The blocks are indexed by hash only once all of them are valid, so a rejected block never touches the
+    /// index of the existing ones.
+    pub fn extend_valid(&mut self, blocks: Vec<Block>, rules: &ValidationRules, now: Time) ->
+        Result<&Block, ValidationError> {
+        let num_of_blocks = self.chain.len();
+        self.chain.extend(blocks.into_iter().map(Arc::new));
+        
+        let result = match num_of_blocks {
+            0 => self.validate(rules, now),
+            _ => self.validate_suffix(num_of_blocks + 1, rules, now),
+        };
+        
+        if let Err(error) = result {
+            self.chain.truncate(num_of_blocks);
+            return Err(error);
+        }
+        
+        if let Some(heights) = self.heights.get_mut() {
+            for position in num_of_blocks..self.chain.len() {
+                heights.insert(self.chain[position].hash(), position + 1);
+            }
+        }
+        
+        Ok(self.last().expect("Chain must contain at least one item"))
+    }
+    
+    /// Drops the blocks after the first `num_of_blocks`, always keeping the genesis block.
+    pub fn truncate(&mut self, num_of_blocks: usize) {
+        let num_of_blocks = num_of_blocks.max(block::INITIAL_INDEX);
+        
+        if num_of_blocks >= self.chain.len() {
+            return;
+        }
+        
+        if let Some(heights) = self.heights.get_mut() {
+            for position in num_of_blocks..self.chain.len() {
+                let hash = self.chain[position].hash();
+                
+                if heights.get(&hash) == Some(&(position + 1)) {
+                    heights.remove(&hash);
+                }
+            }
+        }
+        
+        self.chain.truncate(num_of_blocks);
+    }
+    
+    fn heights(&self) -> &HashMap<Hash, usize> {
+        self.heights.get_or_init(|| self.chain.iter()
+            .enumerate()
+            .map(|(position, block)| (block.hash(), position + 1))
+            .collect())
+    }
+    
+    fn validate_genesis(block: &Block, rules: &ValidationRules, now: Time) -> Result<(), ValidationError> {
+        if block.version() > canonical::CURRENT_VERSION {
+            return Err(ValidationError::UnsupportedVersion(block::INITIAL_INDEX));
+        }
+        
+        if !block.is_first()
+            || block.previous_hash() != block::INITIAL_PREVIOUS_HASH
+            || block.proof() != block::INITIAL_PROOF
+            || !block.transactions().is_empty() {
+            return Err(ValidationError::InvalidGenesis(block::INITIAL_INDEX));
+        }
+        
+        Chain::validate_future_drift(block, block::INITIAL_INDEX, rules, now)
+    }
+    
+    // Validates `block` as the successor of the first `position` blocks.
+    fn validate_successor(&self, position: usize, block: &Block, rules: &ValidationRules, now: Time) ->
+        Result<(), ValidationError> {
+        let previous_block = &self.chain[position - 1];
+        let index = position + 1;
+        
+        if block.version() > canonical::CURRENT_VERSION {
+            return Err(ValidationError::UnsupportedVersion(index));
+        }
+        
+        if block.version() < previous_block.version() {
+            return Err(ValidationError::VersionDowngrade(index));
+        }
+        
         if block.is_first() {
-            return true;
+            return Err(ValidationError::DuplicateGenesis(index));
+        }
+        
+        if block.index() != index {
+            return Err(ValidationError::IndexGap(index));
         }
         
-        let previous_block: Block = match self.previous_block(block) {
-            Ok(block) => block.clone(),
-            Err(_) => {
-                return false;
+        if block.date_time() <= self.median_time(position, rules) {
+            return Err(ValidationError::InvalidTimestamp(index));
+        }
+        
+        Chain::validate_future_drift(block, index, rules, now)?;
+        
+        if block.previous_hash() != previous_block.hash() {
+            return Err(ValidationError::InvalidPreviousHash(index));
+        }
+        
+        if !proof::proof_is_valid(block.proof(), previous_block.proof()) {
+            return Err(ValidationError::InvalidProof(index));
+        }
+        
+        Chain::validate_reward(block, index, rules)
+    }
+    
+    fn median_time(&self, position: usize, rules: &ValidationRules) -> Time {
+        let span = rules.timestamp_rules().median_time_span().min(position);
+        let mut times: Vec<Time> = (position - span..position)
+            .map(|position| self.chain[position].date_time())
+            .collect();
+        times.sort();
+        times[times.len() / 2]
+    }
+    
+    fn validate_future_drift(block: &Block, index: usize, rules: &ValidationRules, now: Time) ->
+        Result<(), ValidationError> {
+        // A drift too large to add to the clock sets no limit.
+        match now.system_time().checked_add(rules.timestamp_rules().max_future_drift()) {
+            Some(max_system_time) if block.date_time().system_time() > max_system_time => {
+                Err(ValidationError::FutureTimestamp(index))
+            },
+            _ => Ok(()),
+        }
+    }
+    
+    fn validate_reward(block: &Block, index: usize, rules: &ValidationRules) -> Result<(), ValidationError> {
+        let reward = match block.transactions().last() {
+            Some(transaction) if transaction.is_reward() => transaction,
+            _ => {
+                return Err(ValidationError::MissingReward(index));
             },
         };
         
-        block.previous_hash() == previous_block.hash()
-            && proof::proof_is_valid(block.proof(), previous_block.proof())
+        if block.transactions().iter().filter(|transaction| transaction.is_reward()).count() > 1 {
+            return Err(ValidationError::InvalidTransaction(index));
+        }
+        
+        if reward.amount() != rules.emission_schedule().reward(index) {
+            return Err(ValidationError::InvalidReward(index));
+        }
+        
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::OnceLock;
+    
+    use crate::{transaction, transactions, fixtures};
+    
+    use transaction::Transaction;
+    use transactions::Transactions;
+    
+    use super::*;
+    
+    const GENESIS_MILLIS: i64 = 1_600_000_000_000;
+    const BLOCK_INTERVAL_MILLIS: i64 = 60_000;
+    
+    static CHAIN: OnceLock<Chain> = OnceLock::new();
+    
+    fn now() -> Time {
+        Time::from_unix_millis(GENESIS_MILLIS + 60 * BLOCK_INTERVAL_MILLIS)
+    }
+    
+    fn date_time(index: usize) -> Time {
+        Time::from_unix_millis(GENESIS_MILLIS + (index as i64 - 1) * BLOCK_INTERVAL_MILLIS)
+    }
+    
+    fn reward(index: usize) -> Transactions {
+        let mut transactions = Transactions::new();
+        transactions.push(Transaction::reward("miner", ValidationRules::default().emission_schedule().reward(index)));
+        transactions
+    }
+    
+    fn successor(previous_block: &Block) -> Block {
+        let index = previous_block.index() + 1;
+        
+        Block::new(index, date_time(index), &reward(index), proof::proof_of_work(previous_block.proof()),
+            previous_block.hash())
+    }
+    
+    // Mining is slow in debug builds, so the valid chain is mined once and shared.
+    fn chain() -> Chain {
+        CHAIN.get_or_init(|| {
+            let mut chain = Chain::new(&Block::genesis(date_time(block::INITIAL_INDEX)));
+            
+            for _ in 0..3 {
+                let block = successor(chain.last().unwrap());
+                chain.push(block);
+            }
+            
+            chain
+        }).clone()
+    }
+    
+    fn forged(index: usize, field: &str, value: serde_json::Value) -> Block {
+        fixtures::patched(chain().block(index).unwrap(), field, Some(value))
+    }
+    
+    // Returns the shared chain with its block at `position` replaced by `block`.
+    fn chain_with(position: usize, block: Block) -> Chain {
+        let mut chain = serde_json::to_value(chain()).unwrap();
+        chain["chain"][position - 1] = serde_json::to_value(block).unwrap();
+        serde_json::from_value(chain).unwrap()
+    }
+    
+    fn validate_with(position: usize, block: Block) -> Result<(), ValidationError> {
+        chain_with(position, block).validate(&ValidationRules::default(), now())
+    }
+    
+    fn timestamp_rules(median_time_span: usize, max_future_drift_secs: u64) -> ValidationRules {
+        let timestamp_rules = serde_json::from_value(serde_json::json!({
+            "median_time_span": median_time_span,
+            "max_future_drift_secs": max_future_drift_secs,
+        })).unwrap();
+        
+        ValidationRules::new(Default::default(), timestamp_rules)
+    }
+    
+    #[test]
+    fn valid_chain_validates() {
+        assert!(chain().validate(&ValidationRules::default(), now()).is_ok());
+    }
+    
+    #[test]
+    fn second_genesis_is_rejected() {
+        let block = Block::genesis(date_time(2));
+        
+        assert!(matches!(validate_with(2, block), Err(ValidationError::DuplicateGenesis(2))));
+    }
+    
+    #[test]
+    fn index_gap_is_rejected() {
+        let block = forged(3, "index", serde_json::Value::from(4));
+        
+        assert!(matches!(validate_with(3, block), Err(ValidationError::IndexGap(3))));
+    }
+    
+    #[test]
+    fn duplicate_index_is_rejected() {
+        let block = forged(3, "index", serde_json::Value::from(2));
+        
+        assert!(matches!(validate_with(3, block), Err(ValidationError::IndexGap(3))));
+    }
+    
+    #[test]
+    fn forged_genesis_is_rejected() {
+        for (field, value) in [("proof", serde_json::Value::from(101)),
+            ("previous_hash", serde_json::to_value(Hash::new([1; 32])).unwrap()),
+            ("transactions", serde_json::to_value(reward(2)).unwrap())] {
+            assert!(matches!(validate_with(1, forged(1, field, value)), Err(ValidationError::InvalidGenesis(1))),
+                "Genesis block with forged {} must be rejected", field);
+        }
+    }
+    
+    #[test]
+    fn bad_previous_hash_is_rejected() {
+        let block = forged(3, "previous_hash", serde_json::to_value(chain().block(1).unwrap().hash()).unwrap());
+        
+        assert!(matches!(validate_with(3, block), Err(ValidationError::InvalidPreviousHash(3))));
+    }
+    
+    #[test]
+    fn bad_proof_is_rejected() {
+        let block = forged(3, "proof", serde_json::Value::from(chain().block(3).unwrap().proof() + 1));
+        
+        assert!(matches!(validate_with(3, block), Err(ValidationError::InvalidProof(3))));
+    }
+    
+    #[test]
+    fn timestamp_going_backwards_is_rejected() {
+        let block = forged(3, "date_time", serde_json::to_value(date_time(1)).unwrap());
+        
+        assert!(matches!(validate_with(3, block), Err(ValidationError::InvalidTimestamp(3))));
+    }
+    
+    #[test]
+    fn timestamp_must_follow_median_of_window() {
+        let after_second_block = forged(4, "date_time",
+            serde_json::to_value(Time::from_unix_millis(GENESIS_MILLIS + BLOCK_INTERVAL_MILLIS + 1)).unwrap());
+        let chain = chain_with(4, after_second_block);
+        
+        assert!(chain.validate(&timestamp_rules(3, 60), now()).is_ok());
+        assert!(matches!(chain.validate(&timestamp_rules(1, 60), now()), Err(ValidationError::InvalidTimestamp(4))));
+        
+        let at_second_block = forged(4, "date_time", serde_json::to_value(date_time(2)).unwrap());
+        
+        assert!(matches!(chain_with(4, at_second_block).validate(&timestamp_rules(3, 60), now()),
+            Err(ValidationError::InvalidTimestamp(4))));
     }
     
-    fn previous_block(&self, block: &Block) -> Result<&Block, ChainError> {
-        self.block(block.index() - 1)
+    #[test]
+    fn min_next_date_time_follows_median() {
+        let rules = timestamp_rules(3, 60);
+        let min_next_date_time = chain().min_next_date_time(&rules).unwrap();
+        
+        assert!(min_next_date_time > date_time(3));
+        assert!(min_next_date_time < date_time(4));
+        assert!(empty_chain().min_next_date_time(&rules).is_none());
+    }
+    
+    #[test]
+    fn future_timestamp_is_rejected() {
+        let rules = timestamp_rules(11, 60);
+        let last_block_time = date_time(4).system_time();
+        
+        assert!(chain().validate(&rules, Time::new(last_block_time - Duration::from_secs(60))).is_ok());
+        assert!(matches!(chain().validate(&rules, Time::new(last_block_time - Duration::from_millis(60_001))),
+            Err(ValidationError::FutureTimestamp(4))));
+    }
+    
+    #[test]
+    fn huge_future_drift_sets_no_limit() {
+        assert!(chain().validate(&timestamp_rules(11, u64::MAX), Time::from_unix_millis(0)).is_ok());
+    }
+    
+    // Validates the shared chain with the transactions of its last block replaced by `transactions`.
+    fn validate_with_transactions(transactions: &[Transaction]) -> Result<(), ValidationError> {
+        let mut forged_transactions = Transactions::new();
+        
+        for transaction in transactions {
+            forged_transactions.push(transaction.clone());
+        }
+        
+        validate_with(4, forged(4, "transactions", serde_json::to_value(forged_transactions).unwrap()))
+    }
+    
+    fn reward_of(index: usize) -> Transaction {
+        reward(index).last().unwrap().clone()
+    }
+    
+    #[test]
+    fn missing_reward_is_rejected() {
+        assert!(matches!(validate_with_transactions(&[]), Err(ValidationError::MissingReward(4))));
+        assert!(matches!(validate_with_transactions(&[Transaction::new("alice", "bob", 5)]),
+            Err(ValidationError::MissingReward(4))));
+    }
+    
+    #[test]
+    fn reward_must_be_last() {
+        assert!(matches!(validate_with_transactions(&[reward_of(4), Transaction::new("alice", "bob", 5)]),
+            Err(ValidationError::MissingReward(4))));
+    }
+    
+    #[test]
+    fn second_reward_is_rejected() {
+        assert!(matches!(validate_with_transactions(&[reward_of(4), reward_of(4)]),
+            Err(ValidationError::InvalidTransaction(4))));
+    }
+    
+    #[test]
+    fn transfer_from_reserved_sender_is_rejected() {
+        let transfer = Transaction::new(crate::reward::SENDER_FOR_MINING, "bob", 5);
+        
+        assert!(matches!(validate_with_transactions(&[transfer, reward_of(4)]),
+            Err(ValidationError::InvalidTransaction(4))));
+    }
+    
+    #[test]
+    fn wrong_reward_amount_is_rejected() {
+        let wrong_reward = Transaction::reward("miner", reward_of(4).amount() + 1);
+        
+        assert!(validate_with_transactions(&[Transaction::new("alice", "bob", 5), reward_of(4)]).is_ok());
+        assert!(matches!(validate_with_transactions(&[wrong_reward]), Err(ValidationError::InvalidReward(4))));
+    }
+    
+    #[test]
+    fn extend_valid_appends_a_valid_suffix() {
+        let chain = chain();
+        let mut prefix = chain.clone();
+        prefix.truncate(2);
+        
+        let suffix = (3..=4).map(|index| chain.block(index).unwrap().clone()).collect();
+        
+        assert!(prefix.extend_valid(suffix, &ValidationRules::default(), now()).is_ok());
+        assert_eq!(prefix.num_of_blocks(), 4);
+        assert_eq!(prefix.height(&chain.block(4).unwrap().hash()), Some(4));
+    }
+    
+    #[test]
+    fn extend_valid_keeps_the_index_of_existing_blocks() {
+        let mut chain = chain();
+        let known_block = chain.block(2).unwrap().clone();
+        
+        assert!(chain.extend_valid(Vec::from([known_block.clone()]), &ValidationRules::default(), now()).is_err());
+        assert_eq!(chain.num_of_blocks(), 4);
+        assert_eq!(chain.height(&known_block.hash()), Some(2));
+    }
+    
+    #[test]
+    fn clone_shares_blocks_until_changed() {
+        let chain = chain();
+        let mut clone = chain.clone();
+        let block = successor(clone.last().unwrap());
+        clone.push(block);
+        
+        assert_eq!(chain.num_of_blocks(), 4);
+        assert_eq!(clone.num_of_blocks(), 5);
+        assert!(std::ptr::eq(chain.block(4).unwrap(), clone.block(4).unwrap()));
+        assert_eq!(chain.height(&clone.block(5).unwrap().hash()), None);
+    }
+    
+    #[test]
+    fn deserialized_chain_is_indexed_on_lookup() {
+        let chain = chain();
+        let deserialized = serde_json::from_str::<Chain>(&serde_json::to_string(&chain).unwrap()).unwrap();
+        
+        assert_eq!(deserialized.height(&chain.block(3).unwrap().hash()), Some(3));
+        assert_eq!(deserialized.reorg_depth(&chain), 0);
+    }
+    
+    fn empty_chain() -> Chain {
+        serde_json::from_str::<Chain>(r#"{"chain": []}"#).unwrap()
+    }
+    
+    #[test]
+    fn push_valid_on_empty_chain_requires_genesis() {
+        let chain = chain();
+        let mut empty_chain = empty_chain();
+        
+        assert!(matches!(empty_chain.push_valid(chain.block(2).unwrap().clone(), &ValidationRules::default(), now()),
+            Err(ValidationError::InvalidGenesis(1))));
+        assert!(empty_chain.push_valid(chain.block(1).unwrap().clone(), &ValidationRules::default(), now()).is_ok());
+        assert_eq!(empty_chain.height(&chain.block(1).unwrap().hash()), Some(1));
+    }
+    
+    #[test]
+    fn extend_valid_on_empty_chain_validates_genesis() {
+        let chain = chain();
+        let mut empty_chain = empty_chain();
+        let blocks = (2..=3).map(|index| chain.block(index).unwrap().clone()).collect();
+        
+        assert!(matches!(empty_chain.extend_valid(blocks, &ValidationRules::default(), now()),
+            Err(ValidationError::InvalidGenesis(1))));
+        assert_eq!(empty_chain.num_of_blocks(), 0);
+        assert!(matches!(empty_chain.extend_valid(Vec::new(), &ValidationRules::default(), now()),
+            Err(ValidationError::EmptyChain)));
+        
+        let blocks = (1..=4).map(|index| chain.block(index).unwrap().clone()).collect();
+        
+        assert!(empty_chain.extend_valid(blocks, &ValidationRules::default(), now()).is_ok());
+        assert_eq!(empty_chain.num_of_blocks(), 4);
+    }
+    
+    #[test]
+    fn version_must_not_go_down() {
+        let chain = chain();
+        let mut prefix = Chain::new(chain.block(1).unwrap());
+        let block = fixtures::patched(chain.block(2).unwrap(), "version", Some(canonical::LEGACY_VERSION.into()));
+        
+        assert!(matches!(prefix.push_valid(block, &ValidationRules::default(), now()),
+            Err(ValidationError::VersionDowngrade(2))));
     }
 }
@@ -0,0 +1,170 @@
+use std::path::PathBuf;
+use std::time::Duration;
+use clap::{Args, Parser, Subcommand};
+
+use crate::output;
+
+use output::OutputFormat;
+
+const DEFAULT_NODE: &str = "http://127.0.0.1:8080";
+const DEFAULT_KEYSTORE: &str = "keystore.json";
+
+#[derive(Parser)]
+#[command(version, about = "Command-line client of a simple blockchain node")]
+pub struct Arguments {
+    /// Url of the node to talk to
+    #[arg(long, env = "BLOCKCHAIN_NODE", default_value = DEFAULT_NODE)]
+    node: String,
+    /// Output format
+    #[arg(long, short, env = "BLOCKCHAIN_OUTPUT", value_enum, default_value_t)]
+    output: OutputFormat,
+    /// Time limit of a single request in seconds
+    #[arg(long, env = "BLOCKCHAIN_TIMEOUT")]
+    timeout: Option<u64>,
+    /// How many times a failed request is repeated
+    #[arg(long, env = "BLOCKCHAIN_RETRIES")]
+    retries: Option<usize>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Show an overview of the node
+    Info,
+    /// Show whether the node is live and ready
+    Health,
+    /// Show the last block of the chain
+    Head,
+    /// Show a block and its transactions
+    Block {
+        /// Index of the block, counting from one
+        index: usize,
+    },
+    /// List the blocks of the chain
+    Blocks,
+    /// List the transactions waiting to be mined
+    Transactions,
+    /// Submit a transaction
+    Submit {
+        #[arg(long)]
+        sender: String,
+        #[arg(long)]
+        recipient: String,
+        #[arg(long, allow_negative_numbers = true)]
+        amount: i64,
+    },
+    /// Manage the node's peers
+    #[command(subcommand)]
+    Peers(PeersCommand),
+    /// Mine a block
+    Mine {
+        /// Address receiving the reward instead of the node's configured address
+        #[arg(long)]
+        payout_address: Option<String>,
+    },
+    /// Adopt the longest valid chain among the node's peers
+    Resolve,
+    /// Show the circulating and the maximum supply
+    Supply,
+    /// Manage the accounts of a local keystore; the passphrase is read from BLOCKCHAIN_PASSPHRASE or prompted for
+    Wallet(WalletArguments),
+}
+
+#[derive(Subcommand)]
+pub enum PeersCommand {
+    /// List the registered peers
+    List,
+    /// Register peers
+    Add {
+        /// Urls of the peers
+        #[arg(required = true)]
+        urls: Vec<String>,
+    },
+    /// Remove a peer
+    Remove {
+        /// Url or net location of the peer
+        url: String,
+    },
+}
+
+#[derive(Args)]
+pub struct WalletArguments {
+    /// Keystore file; defaults to "keystore.json" in the data directory, or in the working directory without one
+    #[arg(long, env = "BLOCKCHAIN_KEYSTORE")]
+    keystore: Option<PathBuf>,
+    /// Data directory shared with the node
+    #[arg(long, env = "BLOCKCHAIN_DATA_DIR")]
+    data_dir: Option<PathBuf>,
+    #[command(subcommand)]
+    command: WalletCommand,
+}
+
+#[derive(Subcommand)]
+pub enum WalletCommand {
+    /// Generate a keypair and add it to the keystore
+    New,
+    /// List the accounts in the keystore
+    List,
+    /// Sign a transaction sent from an account in the keystore
+    Sign {
+        /// Address of the sending account
+        #[arg(long)]
+        from: String,
+        #[arg(long)]
+        recipient: String,
+        /// Positive amount to send
+        #[arg(long)]
+        amount: i64,
+        /// Sender's nonce; every transaction of an account must use a new one
+        #[arg(long)]
+        nonce: u64,
+        /// Network the transaction is valid on; the node's network when omitted
+        #[arg(long)]
+        network_id: Option<String>,
+    },
+    /// Verify a signed transaction in JSON
+    Verify {
+        /// File holding the signed transaction; standard input when omitted
+        file: Option<PathBuf>,
+        /// Network the transaction must be signed for; the node's network when omitted
+        #[arg(long)]
+        network_id: Option<String>,
+    },
+}
+
+impl WalletArguments {
+    pub fn keystore(&self) -> PathBuf {
+        match (&self.keystore, &self.data_dir) {
+            (Some(keystore), _) => keystore.clone(),
+            (None, Some(data_dir)) => data_dir.join(DEFAULT_KEYSTORE),
+            (None, None) => PathBuf::from(DEFAULT_KEYSTORE),
+        }
+    }
+    
+    pub fn command(&self) -> &WalletCommand {
+        &self.command
+    }
+}
+
+impl Arguments {
+    pub fn node(&self) -> &str {
+        self.node.as_str()
+    }
+    
+    pub fn output(&self) -> OutputFormat {
+        self.output
+    }
+    
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout.map(Duration::from_secs)
+    }
+    
+    pub fn retries(&self) -> Option<usize> {
+        self.retries
+    }
+    
+    pub fn command(&self) -> &Command {
+        &self.command
+    }
+}
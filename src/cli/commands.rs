@@ -0,0 +1,340 @@
+use std::env;
+use std::fmt::{Display, Formatter, Error};
+use std::fs;
+use std::io::{self, Read};
+use futures::StreamExt;
+use zeroize::Zeroizing;
+
+use simple_blockchain::{address, transaction, block, node, api, client, wallet, keystore};
+use crate::{arguments, output};
+
+use address::Address;
+use transaction::Transaction;
+use block::Block;
+use node::Node;
+use api::{ErrorResponse, FieldError, NodeInfo};
+use client::{Client, ClientError};
+use wallet::{Keypair, SignedTransaction, WalletError};
+use keystore::{Keystore, KeystoreError};
+use arguments::{Command, PeersCommand, WalletArguments, WalletCommand};
+use output::{OutputFormat, RowPrinter, Table};
+
+const PASSPHRASE_ENV: &str = "BLOCKCHAIN_PASSPHRASE";
+// Blocks are printed as they are streamed, so their columns are sized for typical values up front.
+const BLOCK_COLUMNS: &[(&str, usize)] = &[("INDEX", 8), ("DATE", 30), ("TRANSACTIONS", 12), ("PROOF", 10), ("HASH", 0)];
+
+#[derive(Debug)]
+pub enum CommandError {
+    ClientError(ClientError),
+    InvalidArgument(String),
+    WalletError(WalletError),
+    KeystoreError(KeystoreError),
+    InputError(io::Error),
+}
+
+impl Display for CommandError {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        match self {
+            CommandError::ClientError(error) => write!(fmt, "{}", error),
+            CommandError::InvalidArgument(message) => write!(fmt, "Invalid argument: {}", message),
+            CommandError::WalletError(error) => write!(fmt, "Wallet error: {}", error),
+            CommandError::KeystoreError(error) => write!(fmt, "Keystore error: {}", error),
+            CommandError::InputError(error) => write!(fmt, "Can\'t read input: {}", error),
+        }
+    }
+}
+
+impl From<ClientError> for CommandError {
+    fn from(error: ClientError) -> CommandError {
+        CommandError::ClientError(error)
+    }
+}
+
+impl From<WalletError> for CommandError {
+    fn from(error: WalletError) -> CommandError {
+        CommandError::WalletError(error)
+    }
+}
+
+impl From<KeystoreError> for CommandError {
+    fn from(error: KeystoreError) -> CommandError {
+        CommandError::KeystoreError(error)
+    }
+}
+
+impl From<io::Error> for CommandError {
+    fn from(error: io::Error) -> CommandError {
+        CommandError::InputError(error)
+    }
+}
+
+impl CommandError {
+    pub fn to_error_response(&self) -> ErrorResponse {
+        match self {
+            CommandError::ClientError(ClientError::ApiError(_, _, error_response)) => ErrorResponse::new(
+                error_response.error.code.as_str(), error_response.error.message.as_str(),
+                error_response.error.details.clone()),
+            CommandError::ClientError(error) => ErrorResponse::new(error.code(), error.to_string().as_str(), None),
+            _ => ErrorResponse::new(self.code(), self.to_string().as_str(), None),
+        }
+    }
+    
+    fn code(&self) -> &str {
+        match self {
+            CommandError::ClientError(error) => error.code(),
+            CommandError::InvalidArgument(_) => "invalid_argument",
+            CommandError::WalletError(WalletError::SenderMismatch(..)) => "sender_mismatch",
+            CommandError::WalletError(WalletError::InvalidAmount(_)) => "invalid_amount",
+            CommandError::WalletError(WalletError::InvalidSignature) => "invalid_signature",
+            CommandError::WalletError(_) => "invalid_key",
+            CommandError::KeystoreError(KeystoreError::AccountExists(_)) => "account_exists",
+            CommandError::KeystoreError(KeystoreError::UnknownAccount(_)) => "unknown_account",
+            CommandError::KeystoreError(KeystoreError::WrongPassphrase) => "wrong_passphrase",
+            CommandError::KeystoreError(_) => "keystore_error",
+            CommandError::InputError(_) => "input_error",
+        }
+    }
+    
+    // Invalid fields the node reported, one per line.
+    pub fn field_errors(&self) -> Vec<String> {
+        let details = match self {
+            CommandError::ClientError(error) => error.details(),
+            _ => None,
+        };
+        
+        match details.cloned().map(serde_json::from_value::<Vec<FieldError>>) {
+            Some(Ok(field_errors)) => field_errors.iter().map(|error| error.to_string()).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+pub async fn run(client: &Client, command: &Command, format: OutputFormat) -> Result<(), CommandError> {
+    match command {
+        Command::Info => output::print(format, &client.info().await?, |info| vec![info_table(info)]),
+        Command::Health => output::print(format, &client.health().await?, |health| vec![Table::fields()
+            .field("Live", health.live)
+            .field("Ready", health.ready)
+            .field("State owner running", health.state_owner_running)]),
+        Command::Head => {
+            let info = client.info().await?;
+            let block = client.block(info.height).await?;
+            output::print(format, &block, |block| vec![block_table(block)]);
+        },
+        Command::Block {index} => output::print(format, &client.block(*index).await?, |block|
+            vec![block_table(block), transactions_table(block.transactions().iter())]),
+        Command::Blocks => {
+            let mut stream = client.chain_stream().await?;
+            let mut printer = RowPrinter::new(format, BLOCK_COLUMNS);
+            
+            while let Some(block) = stream.next().await {
+                printer.print(&block?, block_row);
+            }
+            
+            printer.finish();
+        },
+        Command::Transactions => output::print(format, &client.current_transactions().await?, |transactions|
+            vec![transactions_table(transactions.iter())]),
+        Command::Submit {sender, recipient, amount} => {
+            let transaction = Transaction::new(sender.as_str(), recipient.as_str(), *amount);
+            output::print(format, &client.add_transaction(&transaction).await?, |transaction|
+                vec![transactions_table([transaction].into_iter())]);
+        },
+        Command::Peers(PeersCommand::List) => output::print(format, &client.nodes().await?, |nodes|
+            vec![nodes_table(nodes)]),
+        Command::Peers(PeersCommand::Add {urls}) => {
+            let urls: Vec<&str> = urls.iter().map(|url| url.as_str()).collect();
+            output::print(format, &client.register_nodes(&urls).await?, |nodes| vec![nodes_table(nodes)]);
+        },
+        Command::Peers(PeersCommand::Remove {url}) => output::print(format, &client.remove_node(url).await?,
+            |node| vec![nodes_table(std::slice::from_ref(node))]),
+        Command::Mine {payout_address} => {
+            let payout_address = match payout_address.as_deref().map(str::parse::<Address>) {
+                Some(Ok(payout_address)) => Some(payout_address),
+                Some(Err(error)) => {
+                    return Err(CommandError::InvalidArgument(format!("payout address: {}", error)));
+                },
+                None => None,
+            };
+            
+            output::print(format, &client.mine(payout_address).await?, |block|
+                vec![block_table(block), transactions_table(block.transactions().iter())]);
+        },
+        Command::Resolve => output::print(format, &client.resolve_conflicts().await?, |resolving_result|
+            vec![Table::fields().field("Result", resolving_result)]),
+        Command::Supply => output::print(format, &client.supply().await?, |supply| vec![Table::fields()
+            .field("Circulating supply", supply.circulating_supply())
+            .field("Max supply", supply.max_supply())]),
+        Command::Wallet(wallet_arguments) => run_wallet(client, wallet_arguments, format).await?,
+    }
+    
+    Ok(())
+}
+
+async fn run_wallet(client: &Client, wallet_arguments: &WalletArguments, format: OutputFormat) ->
+    Result<(), CommandError> {
+    let mut keystore = Keystore::open(&wallet_arguments.keystore())?;
+    
+    match wallet_arguments.command() {
+        WalletCommand::New => {
+            let passphrase = new_passphrase()?;
+            let account = keystore.add(&Keypair::generate(), passphrase.as_str())?;
+            keystore.save()?;
+            output::print(format, &account, |account| vec![Table::fields()
+                .field("Address", account.address())
+                .field("Public key", account.public_key())
+                .field("Keystore", keystore.path().display())]);
+        },
+        WalletCommand::List => output::print(format, &keystore.accounts(), |accounts| vec![accounts.iter()
+            .fold(Table::new(&["ADDRESS", "PUBLIC KEY"]), |table, account| table.row(vec![
+                account.address().to_string(),
+                account.public_key().to_string(),
+            ]))]),
+        WalletCommand::Sign {from, recipient, amount, nonce, network_id} => {
+            let address = match from.parse::<Address>() {
+                Ok(address) => address,
+                Err(error) => {
+                    return Err(CommandError::InvalidArgument(format!("from: {}", error)));
+                },
+            };
+            
+            if *amount <= 0 {
+                return Err(CommandError::InvalidArgument(String::from("amount must be positive")));
+            }
+            
+            let network_id = signing_network_id(client, network_id.as_deref()).await?;
+            let passphrase = passphrase("Passphrase: ")?;
+            let keypair = keystore.unlock(&address, passphrase.as_str())?;
+            let signed_transaction = keypair.sign(&keypair.transaction(recipient.as_str(), *amount),
+                network_id.as_str(), *nonce)?;
+            output::print(format, &signed_transaction, |signed_transaction|
+                vec![signed_transaction_table(signed_transaction)]);
+        },
+        WalletCommand::Verify {file, network_id} => {
+            let contents = match file {
+                Some(file) => fs::read_to_string(file)?,
+                None => {
+                    let mut contents = String::new();
+                    io::stdin().read_to_string(&mut contents)?;
+                    contents
+                },
+            };
+            
+            let signed_transaction = match serde_json::from_str::<SignedTransaction>(contents.as_str()) {
+                Ok(signed_transaction) => signed_transaction,
+                Err(error) => {
+                    return Err(CommandError::InvalidArgument(format!("signed transaction: {}", error)));
+                },
+            };
+            
+            signed_transaction.verify(signing_network_id(client, network_id.as_deref()).await?.as_str())?;
+            output::print(format, &signed_transaction, |signed_transaction|
+                vec![signed_transaction_table(signed_transaction).field("Valid", true)]);
+        },
+    }
+    
+    Ok(())
+}
+
+async fn signing_network_id(client: &Client, network_id: Option<&str>) -> Result<String, CommandError> {
+    match network_id {
+        Some(network_id) => Ok(String::from(network_id)),
+        None => Ok(client.info().await?.network_id),
+    }
+}
+
+fn passphrase(prompt: &str) -> Result<Zeroizing<String>, CommandError> {
+    match env::var(PASSPHRASE_ENV) {
+        Ok(passphrase) => Ok(Zeroizing::new(passphrase)),
+        Err(_) => Ok(Zeroizing::new(rpassword::prompt_password(prompt)?)),
+    }
+}
+
+fn new_passphrase() -> Result<Zeroizing<String>, CommandError> {
+    let passphrase = passphrase("New passphrase: ")?;
+    
+    if passphrase.is_empty() {
+        return Err(CommandError::InvalidArgument(String::from("passphrase must not be empty")));
+    }
+    
+    if env::var_os(PASSPHRASE_ENV).is_none() {
+        let repeated_passphrase = Zeroizing::new(rpassword::prompt_password("Repeat passphrase: ")?);
+        
+        if passphrase != repeated_passphrase {
+            return Err(CommandError::InvalidArgument(String::from("passphrases don\'t match")));
+        }
+    }
+    
+    Ok(passphrase)
+}
+
+fn signed_transaction_table(signed_transaction: &SignedTransaction) -> Table {
+    let transaction = signed_transaction.transaction();
+    
+    Table::fields()
+        .field("Sender", transaction.sender())
+        .field("Recipient", transaction.recipient())
+        .field("Amount", transaction.amount())
+        .field("Hash", transaction.hash())
+        .field("Network", signed_transaction.network_id())
+        .field("Nonce", signed_transaction.nonce())
+        .field("Public key", signed_transaction.public_key())
+        .field("Signature", signed_transaction.signature())
+}
+
+fn info_table(info: &NodeInfo) -> Table {
+    let payout_address = match info.mining.payout_address {
+        Some(payout_address) => payout_address.to_string(),
+        None => String::from("-"),
+    };
+    
+    Table::fields()
+        .field("Version", info.version.as_str())
+        .field("Network", info.network_id.as_str())
+        .field("Net location", info.net_location.as_str())
+        .field("Height", info.height)
+        .field("Tip hash", info.tip_hash)
+        .field("Mempool size", info.mempool_size)
+        .field("Peers", info.peer_count)
+        .field("Mining enabled", info.mining.enabled)
+        .field("Mining active", info.mining.active)
+        .field("Payout address", payout_address)
+        .field("Uptime, s", info.uptime_secs)
+}
+
+fn block_table(block: &Block) -> Table {
+    Table::fields()
+        .field("Index", block.index())
+        .field("Hash", block.hash())
+        .field("Previous hash", block.previous_hash())
+        .field("Date", block.date_time().to_rfc3339())
+        .field("Proof", block.proof())
+        .field("Version", block.version())
+        .field("Transactions", block.transactions().len())
+}
+
+fn block_row(block: &Block) -> Vec<String> {
+    vec![
+        block.index().to_string(),
+        block.date_time().to_rfc3339(),
+        block.transactions().len().to_string(),
+        block.proof().to_string(),
+        block.hash().to_string(),
+    ]
+}
+
+fn transactions_table<'a>(transactions: impl Iterator<Item = &'a Transaction>) -> Table {
+    transactions.fold(Table::new(&["SENDER", "RECIPIENT", "AMOUNT", "HASH"]), |table, transaction| table.row(vec![
+        String::from(transaction.sender()),
+        String::from(transaction.recipient()),
+        transaction.amount().to_string(),
+        transaction.hash().to_string(),
+    ]))
+}
+
+fn nodes_table(nodes: &[Node]) -> Table {
+    nodes.iter().fold(Table::new(&["NET LOCATION", "URL"]), |table, node| table.row(vec![
+        String::from(node.net_location()),
+        String::from(node.url()),
+    ]))
+}
@@ -0,0 +1,50 @@
+mod arguments;
+mod output;
+mod commands;
+
+use std::process;
+use clap::Parser;
+
+use simple_blockchain::client;
+
+use arguments::Arguments;
+use output::OutputFormat;
+use client::{Client, ClientConfig};
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let arguments = Arguments::parse();
+    let mut config = ClientConfig::default();
+    
+    if let Some(timeout) = arguments.timeout() {
+        config = config.with_timeout(timeout);
+    }
+    
+    if let Some(retries) = arguments.retries() {
+        config = config.with_retries(retries);
+    }
+    
+    let client = match Client::with_config(arguments.node(), config) {
+        Ok(client) => client,
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            process::exit(2);
+        },
+    };
+    
+    if let Err(error) = commands::run(&client, arguments.command(), arguments.output()).await {
+        match arguments.output() {
+            OutputFormat::Json => eprintln!("{}", serde_json::to_string_pretty(&error.to_error_response())
+                .expect("Error response must be serializable")),
+            OutputFormat::Table => {
+                eprintln!("Error: {}", error);
+                
+                for field_error in error.field_errors() {
+                    eprintln!("  {}", field_error);
+                }
+            },
+        }
+        
+        process::exit(1);
+    }
+}
@@ -0,0 +1,133 @@
+use std::fmt::{Display, Formatter, Error};
+use std::io::{self, Write};
+use clap::ValueEnum;
+use serde::Serialize;
+
+const COLUMN_GAP: &str = "  ";
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+}
+
+// Plain-text table: a header row followed by rows, or name-value pairs without a header.
+pub struct Table {
+    header: Option<Vec<String>>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(header: &[&str]) -> Table {
+        Table {
+            header: Some(header.iter().map(|column| column.to_string()).collect()),
+            rows: Vec::new(),
+        }
+    }
+    
+    pub fn fields() -> Table {
+        Table {
+            header: None,
+            rows: Vec::new(),
+        }
+    }
+    
+    pub fn row(mut self, row: Vec<String>) -> Table {
+        self.rows.push(row);
+        self
+    }
+    
+    pub fn field(self, name: &str, value: impl Display) -> Table {
+        self.row(vec![format!("{}:", name), value.to_string()])
+    }
+}
+
+impl Display for Table {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        let rows: Vec<&Vec<String>> = self.header.iter().chain(self.rows.iter()).collect();
+        let num_of_columns = rows.iter().map(|row| row.len()).max().unwrap_or_default();
+        let widths: Vec<usize> = (0..num_of_columns)
+            .map(|column| rows.iter().filter_map(|row| row.get(column)).map(|cell| cell.chars().count()).max()
+                .unwrap_or_default())
+            .collect();
+        
+        for row in rows {
+            writeln!(fmt, "{}", format_row(row, widths.as_slice()))?;
+        }
+        
+        Ok(())
+    }
+}
+
+// Prints items one at a time as they arrive: as the elements of a JSON array, or as the rows of a table whose
+// columns have fixed widths since the rows to come are unknown.
+pub struct RowPrinter {
+    format: OutputFormat,
+    widths: Vec<usize>,
+    num_of_rows: usize,
+}
+
+impl RowPrinter {
+    // Starts the output; every column is at least as wide as its header.
+    pub fn new(format: OutputFormat, columns: &[(&str, usize)]) -> RowPrinter {
+        let widths: Vec<usize> = columns.iter().map(|(header, width)| (*width).max(header.chars().count())).collect();
+        
+        if let OutputFormat::Table = format {
+            let header: Vec<String> = columns.iter().map(|(header, _)| header.to_string()).collect();
+            println!("{}", format_row(&header, widths.as_slice()));
+        }
+        
+        RowPrinter {
+            format,
+            widths,
+            num_of_rows: 0,
+        }
+    }
+    
+    pub fn print<T: Serialize>(&mut self, value: &T, row: impl FnOnce(&T) -> Vec<String>) {
+        match self.format {
+            OutputFormat::Json => {
+                let json = serde_json::to_string_pretty(value).expect("Output must be serializable");
+                let separator = if self.num_of_rows == 0 { "[" } else { "," };
+                print!("{}\n  {}", separator, json.replace('\n', "\n  "));
+                io::stdout().flush().expect("Output must be written");
+            },
+            OutputFormat::Table => println!("{}", format_row(&row(value), self.widths.as_slice())),
+        }
+        
+        self.num_of_rows += 1;
+    }
+    
+    // Ends the output, closing the JSON array.
+    pub fn finish(self) {
+        if let OutputFormat::Json = self.format {
+            println!("{}", if self.num_of_rows == 0 { "[]" } else { "\n]" });
+        }
+    }
+}
+
+fn format_row(row: &[String], widths: &[usize]) -> String {
+    let mut line = String::new();
+    
+    for (column, cell) in row.iter().enumerate() {
+        if column + 1 < row.len() {
+            line.push_str(format!("{:width$}{}", cell, COLUMN_GAP, width = widths[column]).as_str());
+        } else {
+            line.push_str(cell.as_str());
+        }
+    }
+    
+    line
+}
+
+// Prints `value` as JSON or as the tables built from it.
+pub fn print<T: Serialize>(format: OutputFormat, value: &T, tables: impl FnOnce(&T) -> Vec<Table>) {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(value).expect("Output must be serializable")),
+        OutputFormat::Table => {
+            let tables: Vec<String> = tables(value).iter().map(|table| table.to_string()).collect();
+            print!("{}", tables.join("\n"));
+        },
+    }
+}
@@ -0,0 +1,773 @@
+//! Typed async client of the node's HTTP API.
+//!
+//! Requests run on the Tokio runtime reqwest is driven by. Reads are retried on transport errors and server
+//! errors; writes are retried only when the node could not be reached, so they are never applied twice. A streamed
+//! chain has no overall time limit: the timeout applies to its headers and to every chunk of its body.
+
+use std::fmt::{Display, Formatter, Error};
+use std::time::Duration;
+use futures::stream::{self, BoxStream, StreamExt};
+use reqwest::{header, Method, Response, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use url::Url;
+
+use crate::{net_location, address, reward, transaction, transactions, block, chain, encoding, node, api};
+
+use address::Address;
+use reward::Supply;
+use transaction::Transaction;
+use transactions::Transactions;
+use block::Block;
+use chain::Chain;
+use node::Node;
+use api::{OneOrMany, ErrorResponse, NodeRequest, TransactionRequest, MineRequest, ValidationReport, HealthReport,
+    NodeInfo, ResolvingResult};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_RETRIES: usize = 2;
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_millis(500);
+// A streamed line or frame holds a single block; a longer one is a misbehaving node rather than a block worth
+// buffering.
+const MAX_BLOCK_LENGTH: usize = 4 * 1024 * 1024;
+
+/// Error of a request; errors reported by the node keep its status, code, message and details.
+#[derive(Debug)]
+pub enum ClientError {
+    InvalidUrl(String),
+    RequestError(String, String),
+    Timeout(String),
+    ApiError(String, u16, ErrorResponse),
+    UnexpectedStatus(String, u16),
+    DecodeError(String, String),
+}
+
+impl Display for ClientError {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        match self {
+            ClientError::InvalidUrl(url) => write!(fmt, "Invalid node url: {}", url),
+            ClientError::RequestError(url, error) => write!(fmt, "Can\'t get response from: {}: {}", url, error),
+            ClientError::Timeout(url) => write!(fmt, "Request timed out: {}", url),
+            ClientError::ApiError(url, status, error_response) => write!(fmt, "{} failed with status {}: {} ({})",
+                url, status, error_response.error.message, error_response.error.code),
+            ClientError::UnexpectedStatus(url, status) => write!(fmt, "{} failed with status {}", url, status),
+            ClientError::DecodeError(url, error) => write!(fmt, "Can\'t parse response from: {}: {}", url, error),
+        }
+    }
+}
+
+impl ClientError {
+    /// Returns the machine-readable code of the error, the node's own code when it reported the error.
+    pub fn code(&self) -> &str {
+        match self {
+            ClientError::InvalidUrl(_) => "invalid_url",
+            ClientError::RequestError(..) => "request_failed",
+            ClientError::Timeout(_) => "timeout",
+            ClientError::ApiError(_, _, error_response) => error_response.error.code.as_str(),
+            ClientError::UnexpectedStatus(..) => "unexpected_status",
+            ClientError::DecodeError(..) => "invalid_response",
+        }
+    }
+    
+    /// Returns the HTTP status of the response, if one was received.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            ClientError::ApiError(_, status, _) | ClientError::UnexpectedStatus(_, status) => Some(*status),
+            _ => None,
+        }
+    }
+    
+    /// Returns the structured details the node reported, e.g. the invalid fields of a request.
+    pub fn details(&self) -> Option<&Value> {
+        match self {
+            ClientError::ApiError(_, _, error_response) => error_response.error.details.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+/// Timeout and retry policy of a client.
+#[derive(Clone, Copy)]
+pub struct ClientConfig {
+    timeout: Duration,
+    retries: usize,
+    retry_delay: Duration,
+}
+
+impl Default for ClientConfig {
+    fn default() -> ClientConfig {
+        ClientConfig {
+            timeout: DEFAULT_TIMEOUT,
+            retries: DEFAULT_RETRIES,
+            retry_delay: DEFAULT_RETRY_DELAY,
+        }
+    }
+}
+
+impl ClientConfig {
+    /// Sets the time limit of a single attempt, response body included; a streamed body is limited per chunk.
+    pub fn with_timeout(mut self, timeout: Duration) -> ClientConfig {
+        self.timeout = timeout;
+        self
+    }
+    
+    /// Sets how many times a failed request is repeated.
+    pub fn with_retries(mut self, retries: usize) -> ClientConfig {
+        self.retries = retries;
+        self
+    }
+    
+    /// Sets the pause before the first retry; it doubles with every further retry.
+    pub fn with_retry_delay(mut self, retry_delay: Duration) -> ClientConfig {
+        self.retry_delay = retry_delay;
+        self
+    }
+    
+    /// Returns the time limit of a single attempt.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+    
+    /// Returns how many times a failed request is repeated.
+    pub fn retries(&self) -> usize {
+        self.retries
+    }
+    
+    /// Returns the pause before the first retry.
+    pub fn retry_delay(&self) -> Duration {
+        self.retry_delay
+    }
+}
+
+#[derive(Deserialize)]
+struct NodeList {
+    nodes: Vec<Node>,
+}
+
+/// Client of a single node.
+#[derive(Clone)]
+pub struct Client {
+    base_url: String,
+    config: ClientConfig,
+    http: reqwest::Client,
+}
+
+impl Client {
+    /// Creates a client of the node at `base_url` with the default configuration.
+    pub fn new(base_url: &str) -> Result<Client, ClientError> {
+        Client::with_config(base_url, ClientConfig::default())
+    }
+    
+    /// Creates a client of the node at `base_url`, defaulting to http.
+    pub fn with_config(base_url: &str, config: ClientConfig) -> Result<Client, ClientError> {
+        let url = match net_location::parse_url(base_url) {
+            Ok(url) if url.has_host() => url,
+            _ => {
+                return Err(ClientError::InvalidUrl(String::from(base_url)));
+            },
+        };
+        
+        Ok(Client {
+            base_url: String::from(url.as_str().trim_end_matches('/')),
+            config,
+            http: reqwest::Client::new(),
+        })
+    }
+    
+    /// Returns the base url of the node.
+    pub fn base_url(&self) -> &str {
+        self.base_url.as_str()
+    }
+    
+    /// Returns the timeout and retry policy.
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+    
+    /// Returns the liveness and readiness of the node; a node that is not ready is not an error.
+    pub async fn health(&self) -> Result<HealthReport, ClientError> {
+        let url = self.url("/health");
+        let response = self.send(Method::GET, url.as_str(), None, None).await?;
+        
+        if response.status() == StatusCode::SERVICE_UNAVAILABLE {
+            return Client::decode_json(url.as_str(), response).await;
+        }
+        
+        Client::decode_json(url.as_str(), Client::check(url.as_str(), response).await?).await
+    }
+    
+    /// Returns an overview of the node.
+    pub async fn info(&self) -> Result<NodeInfo, ClientError> {
+        self.get_json("/info").await
+    }
+    
+    /// Returns the node's metrics in the Prometheus text format.
+    pub async fn metrics(&self) -> Result<String, ClientError> {
+        let url = self.url("/metrics");
+        let response = self.send(Method::GET, url.as_str(), None, None).await?;
+        let response = Client::check(url.as_str(), response).await?;
+        
+        match response.text().await {
+            Ok(text) => Ok(text),
+            Err(error) => Err(Client::request_error(url.as_str(), error)),
+        }
+    }
+    
+    /// Returns the registered peers.
+    pub async fn nodes(&self) -> Result<Vec<Node>, ClientError> {
+        let node_list = self.get_json::<NodeList>("/nodes").await?;
+        
+        Ok(node_list.nodes)
+    }
+    
+    /// Registers a peer and returns it as the node stored it.
+    pub async fn register_node(&self, url: &str) -> Result<Node, ClientError> {
+        self.post_json("/nodes", &OneOrMany::One(NodeRequest::new(url))).await
+    }
+    
+    /// Registers several peers at once; either all of them are registered or none.
+    pub async fn register_nodes(&self, urls: &[&str]) -> Result<Vec<Node>, ClientError> {
+        let node_requests = urls.iter().map(|url| NodeRequest::new(url)).collect();
+        
+        self.post_json("/nodes", &OneOrMany::Many(node_requests)).await
+    }
+    
+    /// Removes a peer, given by its url or net location, and returns it.
+    pub async fn remove_node(&self, url: &str) -> Result<Node, ClientError> {
+        let node = match Node::from_url(url) {
+            Ok(node) => node,
+            Err(_) => {
+                return Err(ClientError::InvalidUrl(String::from(url)));
+            },
+        };
+        
+        let mut url = Url::parse(self.url("/nodes").as_str()).expect("Node url must be valid");
+        url.path_segments_mut().expect("Node url must have a path").push(node.net_location());
+        let url = String::from(url.as_str());
+        let response = self.send(Method::DELETE, url.as_str(), None, None).await?;
+        
+        Client::decode_json(url.as_str(), Client::check(url.as_str(), response).await?).await
+    }
+    
+    /// Downloads the whole chain, in the binary encoding when the node supports it.
+    pub async fn chain(&self) -> Result<Chain, ClientError> {
+        self.get_encoded("/chain").await
+    }
+    
+    /// Streams the chain block by block, starting with the genesis block.
+    pub async fn chain_stream(&self) -> Result<BoxStream<'static, Result<Block, ClientError>>, ClientError> {
+        self.chain_stream_from(block::INITIAL_INDEX).await
+    }
+    
+    /// Streams the chain block by block, starting with the block at `index`, counting from one, in the binary
+    /// encoding when the node supports it.
+    pub async fn chain_stream_from(&self, index: usize) ->
+        Result<BoxStream<'static, Result<Block, ClientError>>, ClientError> {
+        let url = self.url(format!("/chain/stream?from={}", index).as_str());
+        let accept = format!("{}, {}", encoding::BINARY_CONTENT_TYPE, encoding::JSON_CONTENT_TYPE);
+        let response = self.send_with(Method::GET, url.as_str(), Some(accept), None, false).await?;
+        let response = Client::check(url.as_str(), response).await?;
+        let is_binary = Client::is_binary(&response);
+        
+        let reader = BlockReader::new(url, is_binary, self.config.timeout,
+            response.bytes_stream().map(|bytes| bytes.map(|bytes| bytes.to_vec())).boxed());
+        
+        let blocks = stream::unfold(reader, |mut reader| async move {
+            reader.next_block().await.map(|block| (block, reader))
+        });
+        
+        Ok(blocks.boxed())
+    }
+    
+    /// Returns the block at `index`, counting from one.
+    pub async fn block(&self, index: usize) -> Result<Block, ClientError> {
+        self.get_encoded(format!("/chain/block/{}", index).as_str()).await
+    }
+    
+    /// Makes the node adopt the longest valid chain among its peers.
+    pub async fn resolve_conflicts(&self) -> Result<ResolvingResult, ClientError> {
+        let url = self.url("/chain/resolve_conflicts");
+        let response = self.send(Method::PUT, url.as_str(), None, None).await?;
+        
+        Client::decode_json(url.as_str(), Client::check(url.as_str(), response).await?).await
+    }
+    
+    /// Validates `chain` against the node's consensus rules.
+    pub async fn validate_chain(&self, chain: &Chain) -> Result<ValidationReport, ClientError> {
+        self.post_json("/chain/validate", chain).await
+    }
+    
+    /// Mines a block, paying the reward to `payout_address` or to the node's configured address.
+    pub async fn mine(&self, payout_address: Option<Address>) -> Result<Block, ClientError> {
+        self.post_json("/mine", &MineRequest::new(payout_address)).await
+    }
+    
+    /// Returns the circulating and the maximum supply.
+    pub async fn supply(&self) -> Result<Supply, ClientError> {
+        self.get_json("/supply").await
+    }
+    
+    /// Returns the transactions waiting to be mined.
+    pub async fn current_transactions(&self) -> Result<Transactions, ClientError> {
+        self.get_json("/current_transactions").await
+    }
+    
+    /// Adds a transaction to the ones waiting to be mined.
+    pub async fn add_transaction(&self, transaction: &Transaction) -> Result<Transaction, ClientError> {
+        self.post_json("/transactions", &OneOrMany::One(TransactionRequest::new(transaction))).await
+    }
+    
+    /// Adds several transactions at once; either all of them are added or none.
+    pub async fn add_transactions(&self, transactions: &[Transaction]) -> Result<Vec<Transaction>, ClientError> {
+        let transaction_requests = transactions.iter().map(TransactionRequest::new).collect();
+        
+        self.post_json("/transactions", &OneOrMany::Many(transaction_requests)).await
+    }
+    
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+    
+    async fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T, ClientError> {
+        let url = self.url(path);
+        let response = self.send(Method::GET, url.as_str(), None, None).await?;
+        
+        Client::decode_json(url.as_str(), Client::check(url.as_str(), response).await?).await
+    }
+    
+    async fn get_encoded<T: DeserializeOwned>(&self, path: &str) -> Result<T, ClientError> {
+        let url = self.url(path);
+        let accept = format!("{}, {}", encoding::BINARY_CONTENT_TYPE, encoding::JSON_CONTENT_TYPE);
+        let response = self.send(Method::GET, url.as_str(), Some(accept), None).await?;
+        let response = Client::check(url.as_str(), response).await?;
+        
+        if !Client::is_binary(&response) {
+            return Client::decode_json(url.as_str(), response).await;
+        }
+        
+        let bytes = match response.bytes().await {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                return Err(Client::request_error(url.as_str(), error));
+            },
+        };
+        
+        match encoding::from_binary(&bytes) {
+            Ok(value) => Ok(value),
+            Err(error) => Err(ClientError::DecodeError(url, error.to_string())),
+        }
+    }
+    
+    fn is_binary(response: &Response) -> bool {
+        response.headers().get(header::CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .is_some_and(encoding::is_binary)
+    }
+    
+    async fn post_json<B: Serialize, T: DeserializeOwned>(&self, path: &str, body: &B) -> Result<T, ClientError> {
+        let url = self.url(path);
+        let body = serde_json::to_vec(body).expect("Request body must be serializable");
+        let response = self.send(Method::POST, url.as_str(), None, Some(body)).await?;
+        
+        Client::decode_json(url.as_str(), Client::check(url.as_str(), response).await?).await
+    }
+    
+    async fn send(&self, method: Method, url: &str, accept: Option<String>, body: Option<Vec<u8>>) ->
+        Result<Response, ClientError> {
+        self.send_with(method, url, accept, body, true).await
+    }
+    
+    // Sends a request, retrying it as the policy allows. The timeout covers the response headers and, when
+    // `limits_body` is set, the whole body as well.
+    async fn send_with(&self, method: Method, url: &str, accept: Option<String>, body: Option<Vec<u8>>,
+        limits_body: bool) -> Result<Response, ClientError> {
+        let is_idempotent = method != Method::POST;
+        let mut retry_delay = self.config.retry_delay;
+        let mut attempt = 0;
+        
+        loop {
+            let mut request = self.http.request(method.clone(), url)
+                .header(header::ACCEPT, accept.as_deref().unwrap_or(encoding::JSON_CONTENT_TYPE));
+            
+            if limits_body {
+                request = request.timeout(self.config.timeout);
+            }
+            
+            if let Some(body) = &body {
+                request = request.header(header::CONTENT_TYPE, encoding::JSON_CONTENT_TYPE).body(body.clone());
+            }
+            
+            let can_retry = attempt < self.config.retries;
+            
+            match tokio::time::timeout(self.config.timeout, request.send()).await {
+                Ok(Ok(response)) if can_retry && is_idempotent && response.status().is_server_error() => {},
+                Ok(Ok(response)) => {
+                    return Ok(response);
+                },
+                Ok(Err(error)) if can_retry && (is_idempotent || error.is_connect()) => {},
+                Ok(Err(error)) => {
+                    return Err(Client::request_error(url, error));
+                },
+                Err(_) if can_retry && is_idempotent => {},
+                Err(_) => {
+                    return Err(ClientError::Timeout(String::from(url)));
+                },
+            }
+            
+            tokio::time::sleep(retry_delay).await;
+            retry_delay = retry_delay.saturating_mul(2);
+            attempt += 1;
+        }
+    }
+    
+    async fn check(url: &str, response: Response) -> Result<Response, ClientError> {
+        let status = response.status();
+        
+        if status.is_success() {
+            return Ok(response);
+        }
+        
+        let error_response = match response.bytes().await {
+            Ok(bytes) => serde_json::from_slice::<ErrorResponse>(&bytes).ok(),
+            Err(_) => None,
+        };
+        
+        match error_response {
+            Some(error_response) => Err(ClientError::ApiError(String::from(url), status.as_u16(), error_response)),
+            None => Err(ClientError::UnexpectedStatus(String::from(url), status.as_u16())),
+        }
+    }
+    
+    async fn decode_json<T: DeserializeOwned>(url: &str, response: Response) -> Result<T, ClientError> {
+        let bytes = match response.bytes().await {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                return Err(Client::request_error(url, error));
+            },
+        };
+        
+        match serde_json::from_slice(&bytes) {
+            Ok(value) => Ok(value),
+            Err(error) => Err(ClientError::DecodeError(String::from(url), error.to_string())),
+        }
+    }
+    
+    fn request_error(url: &str, error: reqwest::Error) -> ClientError {
+        if error.is_timeout() {
+            ClientError::Timeout(String::from(url))
+        } else {
+            ClientError::RequestError(String::from(url), error.to_string())
+        }
+    }
+}
+
+// Splits a streamed body into blocks, either JSON lines or binary frames; every chunk must arrive within `timeout`.
+struct BlockReader {
+    url: String,
+    is_binary: bool,
+    timeout: Duration,
+    bytes: BoxStream<'static, reqwest::Result<Vec<u8>>>,
+    buffer: Vec<u8>,
+    finished: bool,
+}
+
+impl BlockReader {
+    fn new(url: String, is_binary: bool, timeout: Duration, bytes: BoxStream<'static, reqwest::Result<Vec<u8>>>) ->
+        BlockReader {
+        BlockReader {
+            url,
+            is_binary,
+            timeout,
+            bytes,
+            buffer: Vec::new(),
+            finished: false,
+        }
+    }
+    
+    async fn next_block(&mut self) -> Option<Result<Block, ClientError>> {
+        loop {
+            let block = if self.is_binary { self.next_frame() } else { self.next_line() };
+            
+            match block {
+                Some(Ok(block)) => {
+                    return Some(Ok(block));
+                },
+                Some(Err(error)) => {
+                    self.finished = true;
+                    self.buffer.clear();
+                    return Some(Err(error));
+                },
+                None if self.finished => {
+                    return None;
+                },
+                None => {},
+            }
+            
+            match tokio::time::timeout(self.timeout, self.bytes.next()).await {
+                Ok(Some(Ok(bytes))) => self.buffer.extend_from_slice(&bytes),
+                Ok(Some(Err(error))) => {
+                    self.finished = true;
+                    self.buffer.clear();
+                    return Some(Err(Client::request_error(self.url.as_str(), error)));
+                },
+                Ok(None) => self.finished = true,
+                Err(_) => {
+                    self.finished = true;
+                    self.buffer.clear();
+                    return Some(Err(ClientError::Timeout(self.url.clone())));
+                },
+            }
+        }
+    }
+    
+    // Takes the next complete line off the buffer, or the rest of it once the body has ended; blank lines are
+    // skipped.
+    fn next_line(&mut self) -> Option<Result<Block, ClientError>> {
+        loop {
+            let line: Vec<u8> = match self.buffer.iter().position(|byte| *byte == b'\n') {
+                Some(position) => self.buffer.drain(..=position).collect(),
+                None if self.buffer.len() > MAX_BLOCK_LENGTH => {
+                    return Some(Err(self.decode_error(format!("Line exceeds {} bytes", MAX_BLOCK_LENGTH))));
+                },
+                None if self.finished && !self.buffer.is_empty() => self.buffer.drain(..).collect(),
+                None => {
+                    return None;
+                },
+            };
+            
+            if line.iter().all(|byte| byte.is_ascii_whitespace()) {
+                continue;
+            }
+            
+            match serde_json::from_slice::<Block>(&line) {
+                Ok(block) => {
+                    return Some(Ok(block));
+                },
+                Err(error) => {
+                    return Some(Err(self.decode_error(error.to_string())));
+                },
+            }
+        }
+    }
+    
+    // Takes the next complete frame off the buffer; a body ending inside a frame is truncated.
+    fn next_frame(&mut self) -> Option<Result<Block, ClientError>> {
+        let length = match encoding::frame_length(&self.buffer) {
+            Some(length) if length > MAX_BLOCK_LENGTH => {
+                return Some(Err(self.decode_error(format!("Frame exceeds {} bytes", MAX_BLOCK_LENGTH))));
+            },
+            Some(length) if self.buffer.len() >= encoding::FRAME_HEADER_LENGTH + length => length,
+            _ if self.finished && !self.buffer.is_empty() => {
+                return Some(Err(self.decode_error(String::from("Truncated frame"))));
+            },
+            _ => {
+                return None;
+            },
+        };
+        
+        let frame: Vec<u8> = self.buffer.drain(..encoding::FRAME_HEADER_LENGTH + length).collect();
+        
+        match encoding::from_binary::<Block>(&frame[encoding::FRAME_HEADER_LENGTH..]) {
+            Ok(block) => Some(Ok(block)),
+            Err(error) => Some(Err(self.decode_error(error.to_string()))),
+        }
+    }
+    
+    fn decode_error(&self, error: String) -> ClientError {
+        ClientError::DecodeError(self.url.clone(), error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
+    use std::thread;
+    
+    use crate::{hash, fixtures};
+    
+    use hash::Hash;
+    
+    use super::*;
+    
+    const URL: &str = "http://127.0.0.1:8080/chain/stream";
+    
+    fn blocks() -> Vec<Block> {
+        fixtures::chain().iter().cloned().collect()
+    }
+    
+    fn block_hashes() -> Vec<Hash> {
+        blocks().iter().map(Block::hash).collect()
+    }
+    
+    fn chunked(bytes: &[u8], chunk_length: usize) -> Vec<Vec<u8>> {
+        bytes.chunks(chunk_length).map(<[u8]>::to_vec).collect()
+    }
+    
+    fn json_lines(blocks: &[Block]) -> String {
+        blocks.iter().map(|block| serde_json::to_string(block).unwrap() + "\n").collect()
+    }
+    
+    fn frames(blocks: &[Block]) -> Vec<u8> {
+        blocks.iter().flat_map(|block| encoding::to_binary_frame(block).unwrap()).collect()
+    }
+    
+    fn reader(is_binary: bool, timeout: Duration, bytes: BoxStream<'static, reqwest::Result<Vec<u8>>>) -> BlockReader {
+        BlockReader::new(String::from(URL), is_binary, timeout, bytes)
+    }
+    
+    async fn read(is_binary: bool, chunks: Vec<Vec<u8>>) -> Vec<Result<Block, ClientError>> {
+        read_all(reader(is_binary, DEFAULT_TIMEOUT, stream::iter(chunks.into_iter().map(Ok)).boxed())).await
+    }
+    
+    async fn read_all(mut reader: BlockReader) -> Vec<Result<Block, ClientError>> {
+        let mut blocks = Vec::new();
+        
+        while let Some(block) = reader.next_block().await {
+            blocks.push(block);
+        }
+        
+        blocks
+    }
+    
+    fn hashes(blocks: &[Result<Block, ClientError>]) -> Vec<Hash> {
+        blocks.iter().map(|block| block.as_ref().unwrap().hash()).collect()
+    }
+    
+    #[tokio::test]
+    async fn lines_split_across_chunks_are_joined() {
+        let body = json_lines(&blocks());
+        
+        assert_eq!(hashes(&read(false, chunked(body.as_bytes(), 7)).await), block_hashes());
+    }
+    
+    #[tokio::test]
+    async fn blank_lines_and_unterminated_last_line_are_read() {
+        let blocks = blocks();
+        let body = format!("\n{}\r\n  \n{}", json_lines(&blocks[..1]), serde_json::to_string(&blocks[1]).unwrap());
+        
+        assert_eq!(hashes(&read(false, vec![body.into_bytes()]).await), block_hashes());
+    }
+    
+    #[tokio::test]
+    async fn line_longer_than_limit_is_rejected() {
+        let blocks = read(false, vec![vec![b'x'; MAX_BLOCK_LENGTH + 1]]).await;
+        
+        assert_eq!(blocks.len(), 1);
+        assert!(matches!(&blocks[0], Err(ClientError::DecodeError(_, error)) if error.starts_with("Line exceeds")));
+    }
+    
+    #[tokio::test]
+    async fn frames_split_across_chunks_are_joined() {
+        assert_eq!(hashes(&read(true, chunked(&frames(&blocks()), 3)).await), block_hashes());
+    }
+    
+    #[tokio::test]
+    async fn truncated_and_oversized_frames_are_rejected() {
+        let mut body = frames(&blocks());
+        body.pop();
+        let blocks = read(true, vec![body]).await;
+        
+        assert_eq!(blocks.len(), 2);
+        assert!(matches!(&blocks[1], Err(ClientError::DecodeError(_, error)) if error == "Truncated frame"));
+        
+        let header = (MAX_BLOCK_LENGTH as u32 + 1).to_be_bytes().to_vec();
+        let blocks = read(true, vec![header]).await;
+        
+        assert_eq!(blocks.len(), 1);
+        assert!(matches!(&blocks[0], Err(ClientError::DecodeError(_, error)) if error.starts_with("Frame exceeds")));
+    }
+    
+    #[tokio::test]
+    async fn stream_is_limited_per_chunk() {
+        let timeout = Duration::from_millis(200);
+        let delay = Duration::from_millis(20);
+        let chunks = chunked(json_lines(&blocks()).as_bytes(), 16);
+        
+        assert!(delay * chunks.len() as u32 > timeout);
+        
+        let slow_chunks = stream::iter(chunks).then(move |chunk| async move {
+            tokio::time::sleep(delay).await;
+            Ok(chunk)
+        });
+        
+        assert_eq!(hashes(&read_all(reader(false, timeout, slow_chunks.boxed())).await), block_hashes());
+        
+        let stalled_chunks = stream::iter(vec![Ok(json_lines(&blocks()[..1]).into_bytes())]).chain(stream::pending());
+        let blocks = read_all(reader(false, timeout, stalled_chunks.boxed())).await;
+        
+        assert_eq!(blocks.len(), 2);
+        assert!(matches!(&blocks[1], Err(ClientError::Timeout(_))));
+    }
+    
+    // Answers every request with `status` and `body` and counts the requests.
+    fn serve(status: &'static str, body: &'static str) -> (Client, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = ClientConfig::default().with_retry_delay(Duration::from_millis(1));
+        let client = Client::with_config(listener.local_addr().unwrap().to_string().as_str(), config).unwrap();
+        let num_of_requests = Arc::new(AtomicUsize::new(0));
+        let counter = num_of_requests.clone();
+        
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                
+                // Reads the head and the body, so closing the connection doesn't reset it.
+                while let Ok(length @ 1..) = stream.read(&mut buffer) {
+                    request.extend_from_slice(&buffer[..length]);
+                    let request = String::from_utf8_lossy(&request);
+                    
+                    if let Some((head, body)) = request.split_once("\r\n\r\n") {
+                        let content_length = head.lines()
+                            .find_map(|line| line.to_lowercase().strip_prefix("content-length:")
+                                .map(|length| length.trim().parse::<usize>().unwrap()))
+                            .unwrap_or_default();
+                        
+                        if body.len() >= content_length {
+                            break;
+                        }
+                    }
+                }
+                
+                counter.fetch_add(1, Ordering::SeqCst);
+                let response = format!("HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+                    Connection: close\r\n\r\n{}", status, body.len(), body);
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        
+        (client, num_of_requests)
+    }
+    
+    #[tokio::test]
+    async fn error_response_is_decoded() {
+        let (client, _) = serve("409 Conflict",
+            r#"{"error": {"code": "node_already_registered", "message": "Node has been registered"}}"#);
+        let error = client.register_node("http://127.0.0.2:8080").await.err().unwrap();
+        
+        assert_eq!(error.status(), Some(409));
+        assert_eq!(error.code(), "node_already_registered");
+        
+        let (client, _) = serve("400 Bad Request", "Bad request");
+        let error = client.register_node("http://127.0.0.2:8080").await.err().unwrap();
+        
+        assert!(matches!(error, ClientError::UnexpectedStatus(_, 400)));
+    }
+    
+    #[tokio::test]
+    async fn post_is_not_retried() {
+        let (client, num_of_requests) = serve("503 Service Unavailable",
+            r#"{"error": {"code": "state_unavailable", "message": "Blockchain state is unavailable"}}"#);
+        let error = client.mine(None).await.err().unwrap();
+        
+        assert_eq!(error.code(), "state_unavailable");
+        assert_eq!(num_of_requests.load(Ordering::SeqCst), 1);
+        
+        assert!(client.info().await.is_err());
+        
+        assert_eq!(num_of_requests.load(Ordering::SeqCst), 1 + 1 + DEFAULT_RETRIES);
+    }
+}
@@ -0,0 +1,54 @@
+//! Time sources.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::time;
+
+use time::Time;
+
+/// Source of the current time, injected wherever validation depends on "now".
+pub trait Clock: Send + Sync {
+    /// Returns the current time.
+    fn now(&self) -> Time;
+}
+
+/// Clock reading the system time.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Time {
+        Time::now()
+    }
+}
+
+/// Clock returning a manually controlled time, for tests and simulations.
+pub struct MockClock {
+    time: Mutex<Time>,
+}
+
+impl MockClock {
+    /// Creates a clock stopped at `time`.
+    pub fn new(time: Time) -> MockClock {
+        MockClock {
+            time: Mutex::new(time),
+        }
+    }
+    
+    /// Moves the clock to `time`.
+    pub fn set(&self, time: Time) {
+        *self.time.lock().expect("Mock clock must not be poisoned") = time;
+    }
+    
+    /// Moves the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        let mut time = self.time.lock().expect("Mock clock must not be poisoned");
+        *time = Time::new(time.system_time() + duration);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Time {
+        *self.time.lock().expect("Mock clock must not be poisoned")
+    }
+}
@@ -0,0 +1,144 @@
+//! Binary encoding of API bodies, negotiated alongside JSON.
+//!
+//! A streamed body in the binary encoding is a sequence of frames, each the length of a bincode-encoded value as
+//! a big-endian `u32` followed by the value.
+
+use std::fmt::{Display, Formatter, Error};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Content type of JSON bodies.
+pub const JSON_CONTENT_TYPE: &str = "application/json";
+/// Content type of bincode bodies.
+pub const BINARY_CONTENT_TYPE: &str = "application/x-bincode";
+/// Length of the header of a frame.
+pub const FRAME_HEADER_LENGTH: usize = 4;
+
+/// Error encoding or decoding a binary body.
+#[derive(Debug)]
+pub enum EncodingError {
+    SerializeError(String),
+    DeserializeError(String),
+}
+
+impl Display for EncodingError {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        match self {
+            EncodingError::SerializeError(error) => write!(fmt, "Can\'t serialize value: {}", error),
+            EncodingError::DeserializeError(error) => write!(fmt, "Can\'t deserialize value: {}", error),
+        }
+    }
+}
+
+/// Tells whether an `Accept` header value allows the binary encoding; a quality of zero refuses it.
+pub fn accepts_binary(accept: &str) -> bool {
+    accept.split(',').any(|media_range| {
+        let mut parameters = media_range.split(';');
+        let media_type = parameters.next().unwrap_or_default().trim();
+        
+        media_type.eq_ignore_ascii_case(BINARY_CONTENT_TYPE) && quality(parameters) > 0.0
+    })
+}
+
+fn quality<'a>(parameters: impl Iterator<Item = &'a str>) -> f32 {
+    for parameter in parameters {
+        if let Some((name, value)) = parameter.split_once('=') {
+            if name.trim().eq_ignore_ascii_case("q") {
+                return value.trim().parse().unwrap_or(0.0);
+            }
+        }
+    }
+    
+    1.0
+}
+
+/// Tells whether a `Content-Type` header value is the binary encoding.
+pub fn is_binary(content_type: &str) -> bool {
+    content_type.split(';').next().unwrap_or_default().trim().eq_ignore_ascii_case(BINARY_CONTENT_TYPE)
+}
+
+/// Encodes a value with bincode.
+pub fn to_binary<T: Serialize>(value: &T) -> Result<Vec<u8>, EncodingError> {
+    match bincode::serialize(value) {
+        Ok(bytes) => Ok(bytes),
+        Err(error) => Err(EncodingError::SerializeError(error.to_string())),
+    }
+}
+
+/// Encodes a value with bincode as a frame of a streamed body.
+pub fn to_binary_frame<T: Serialize>(value: &T) -> Result<Vec<u8>, EncodingError> {
+    let bytes = to_binary(value)?;
+    
+    let length = match u32::try_from(bytes.len()) {
+        Ok(length) => length,
+        Err(error) => {
+            return Err(EncodingError::SerializeError(error.to_string()));
+        },
+    };
+    
+    let mut frame = Vec::with_capacity(FRAME_HEADER_LENGTH + bytes.len());
+    frame.extend_from_slice(&length.to_be_bytes());
+    frame.extend_from_slice(&bytes);
+    Ok(frame)
+}
+
+/// Returns the length of the value in the frame `bytes` start with, once its header is complete.
+pub fn frame_length(bytes: &[u8]) -> Option<usize> {
+    let header = <[u8; FRAME_HEADER_LENGTH]>::try_from(bytes.get(..FRAME_HEADER_LENGTH)?).ok()?;
+    
+    Some(u32::from_be_bytes(header) as usize)
+}
+
+/// Decodes a bincode-encoded value.
+pub fn from_binary<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, EncodingError> {
+    match bincode::deserialize(bytes) {
+        Ok(value) => Ok(value),
+        Err(error) => Err(EncodingError::DeserializeError(error.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{block, chain, fixtures};
+    
+    use block::Block;
+    use chain::Chain;
+    use fixtures::hashes;
+    
+    use super::*;
+    
+    #[test]
+    fn binary_and_json_decode_to_identical_hashes() {
+        let chain = fixtures::chain();
+        let from_binary = from_binary::<Chain>(&to_binary(&chain).unwrap()).unwrap();
+        let from_json = serde_json::from_str::<Chain>(&serde_json::to_string(&chain).unwrap()).unwrap();
+        
+        assert_eq!(hashes(&from_binary), hashes(&chain));
+        assert_eq!(hashes(&from_json), hashes(&chain));
+    }
+    
+    #[test]
+    fn binary_frame_holds_its_length() {
+        let chain = fixtures::chain();
+        let block = chain.block(2).unwrap();
+        let frame = to_binary_frame(block).unwrap();
+        
+        assert_eq!(frame_length(&frame), Some(frame.len() - FRAME_HEADER_LENGTH));
+        assert_eq!(frame_length(&frame[..FRAME_HEADER_LENGTH - 1]), None);
+        assert_eq!(from_binary::<Block>(&frame[FRAME_HEADER_LENGTH..]).unwrap().hash(), block.hash());
+    }
+    
+    #[test]
+    fn accepts_binary_honours_quality() {
+        assert!(accepts_binary("application/x-bincode"));
+        assert!(accepts_binary("application/json, application/x-bincode;q=0.5"));
+        assert!(!accepts_binary("application/json"));
+        assert!(!accepts_binary("application/x-bincode;q=0"));
+        assert!(!accepts_binary("application/x-bincode; q=0.0, application/json"));
+    }
+    
+    #[test]
+    fn is_binary_ignores_parameters() {
+        assert!(is_binary("application/x-bincode; charset=binary"));
+        assert!(!is_binary("application/json"));
+    }
+}
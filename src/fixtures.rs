@@ -0,0 +1,63 @@
+//! Fixtures shared by the library's unit tests.
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{time, hash, transaction, transactions, block, chain};
+
+use time::Time;
+use hash::Hash;
+use transaction::Transaction;
+use transactions::Transactions;
+use block::Block;
+use chain::Chain;
+
+/// Date of the genesis block of [`chain`].
+pub const GENESIS_MILLIS: i64 = 1_600_000_000_000;
+/// Date of [`block`].
+pub const BLOCK_MILLIS: i64 = 1_600_000_060_123;
+/// Proof of [`block`]; it isn't a valid proof of work.
+pub const BLOCK_PROOF: i64 = 35293;
+
+/// Transfer of 5 from alice to bob followed by a reward of 50 to miner.
+pub fn transactions() -> Transactions {
+    let mut transactions = Transactions::new();
+    transactions.push(Transaction::new("alice", "bob", 5));
+    transactions.push(Transaction::reward("miner", 50));
+    transactions
+}
+
+/// Block 2 holding [`transactions`] on top of a block with `previous_hash`.
+pub fn block(previous_hash: Hash) -> Block {
+    Block::new(2, Time::from_unix_millis(BLOCK_MILLIS), &transactions(), BLOCK_PROOF, previous_hash)
+}
+
+/// Genesis block followed by [`block`]; the chain isn't mined, so it doesn't validate.
+pub fn chain() -> Chain {
+    let genesis = Block::genesis(Time::from_unix_millis(GENESIS_MILLIS));
+    let mut chain = Chain::new(&genesis);
+    chain.push(block(genesis.hash()));
+    chain
+}
+
+/// Returns the hashes of a chain's blocks in order.
+pub fn hashes(chain: &Chain) -> Vec<String> {
+    chain.iter().map(|block| block.hash().to_string()).collect()
+}
+
+/// Serializes `value` to JSON and sets `field` to `field_value`, or removes it when `field_value` is `None`.
+pub fn patched_json<T: Serialize>(value: &T, field: &str, field_value: Option<serde_json::Value>) -> serde_json::Value {
+    let mut json = serde_json::to_value(value).unwrap();
+    let fields = json.as_object_mut().unwrap();
+    
+    match field_value {
+        Some(field_value) => fields.insert(String::from(field), field_value),
+        None => fields.remove(field),
+    };
+    
+    json
+}
+
+/// Copy of `value` with `field` set to `field_value`, or removed when `field_value` is `None`.
+pub fn patched<T: Serialize + DeserializeOwned>(value: &T, field: &str, field_value: Option<serde_json::Value>) -> T {
+    serde_json::from_value(patched_json(value, field, field_value)).unwrap()
+}
@@ -0,0 +1,95 @@
+//! SHA-256 hashes of blocks and transactions.
+
+use std::fmt::{Display, Formatter, Error};
+use std::str::FromStr;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::hex_string;
+
+use hex_string::HexError;
+
+/// Length of a hash in bytes.
+pub const HASH_LENGTH: usize = 32;
+
+/// Error parsing a hash from a hex string.
+#[derive(Debug)]
+pub enum HashError {
+    InvalidHex(hex::FromHexError),
+    InvalidLength(usize),
+}
+
+impl From<HexError> for HashError {
+    fn from(error: HexError) -> HashError {
+        match error {
+            HexError::InvalidHex(error) => HashError::InvalidHex(error),
+            HexError::InvalidLength(length, _) => HashError::InvalidLength(length),
+        }
+    }
+}
+
+impl Display for HashError {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        match self {
+            HashError::InvalidHex(error) => write!(fmt, "Invalid hex string: {}", error),
+            HashError::InvalidLength(length) => write!(fmt, "Invalid hash length: {} bytes instead of {}", length,
+                HASH_LENGTH),
+        }
+    }
+}
+
+/// SHA-256 hash.
+///
+/// Serialized as a hex string in human-readable formats and as raw bytes otherwise.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Hash([u8; HASH_LENGTH]);
+
+impl Hash {
+    /// Wraps raw hash bytes.
+    pub const fn new(bytes: [u8; HASH_LENGTH]) -> Hash {
+        Hash(bytes)
+    }
+    
+    /// Computes the SHA-256 hash of `data`.
+    pub fn digest(data: &[u8]) -> Hash {
+        sha256::digest(data).parse().expect("SHA-256 digest must be a valid hash")
+    }
+    
+    /// Returns the raw hash bytes.
+    pub fn as_bytes(&self) -> &[u8; HASH_LENGTH] {
+        &self.0
+    }
+}
+
+impl Display for Hash {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        write!(fmt, "{}", hex::encode(self.0))
+    }
+}
+
+impl FromStr for Hash {
+    type Err = HashError;
+    
+    fn from_str(string: &str) -> Result<Hash, HashError> {
+        Ok(Hash(hex_string::decode_hex(string)?))
+    }
+}
+
+impl Serialize for Hash {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            hex_string::serialize_str(self, serializer)
+        } else {
+            self.0.serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Hash {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Hash, D::Error> {
+        if deserializer.is_human_readable() {
+            hex_string::deserialize_str(deserializer)
+        } else {
+            <[u8; HASH_LENGTH]>::deserialize(deserializer).map(Hash)
+        }
+    }
+}
@@ -0,0 +1,51 @@
+//! Fixed-length byte strings written as hex digits, and serde support for types written as strings.
+
+use std::fmt::Display;
+use std::str::FromStr;
+use serde::{Deserialize, Deserializer, Serializer, de};
+
+/// Error decoding a fixed-length hex string.
+#[derive(Debug)]
+pub enum HexError {
+    InvalidHex(hex::FromHexError),
+    InvalidLength(usize, usize),
+}
+
+/// Decodes exactly `LENGTH` bytes from hex digits.
+pub fn decode_hex<const LENGTH: usize>(string: &str) -> Result<[u8; LENGTH], HexError> {
+    let bytes = match hex::decode(string) {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            return Err(HexError::InvalidHex(error));
+        },
+    };
+    
+    match <[u8; LENGTH]>::try_from(bytes.as_slice()) {
+        Ok(bytes) => Ok(bytes),
+        Err(_) => Err(HexError::InvalidLength(bytes.len(), LENGTH)),
+    }
+}
+
+/// Serializes `value` as its `Display` string.
+pub fn serialize_str<T: Display, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+/// Deserializes a value from a string with its `FromStr` implementation.
+pub fn deserialize_str<'de, T: FromStr<Err = E>, E: Display, D: Deserializer<'de>>(deserializer: D) ->
+    Result<T, D::Error> {
+    let string = String::deserialize(deserializer)?;
+    string.parse().map_err(de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn exact_length_is_decoded() {
+        assert_eq!(decode_hex::<2>("0aFF").unwrap(), [0x0a, 0xff]);
+        assert!(matches!(decode_hex::<3>("0aff"), Err(HexError::InvalidLength(2, 3))));
+        assert!(matches!(decode_hex::<2>("0afg"), Err(HexError::InvalidHex(_))));
+    }
+}
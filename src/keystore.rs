@@ -0,0 +1,429 @@
+//! Keystore file holding the keypairs of accounts, each encrypted with a passphrase.
+//!
+//! A secret key is encrypted with ChaCha20-Poly1305 under a key derived from the passphrase with Argon2id. The
+//! salt, the nonce and the Argon2 parameters are stored next to every key, so accounts can be listed without the
+//! passphrase and parameters can be raised without breaking existing files.
+
+use std::fmt::{Display, Formatter, Error};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{ChaCha20Poly1305, Key, KeyInit, Nonce, aead::{Aead, Payload}};
+use ed25519_dalek::SECRET_KEY_LENGTH;
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::{address, wallet};
+
+use address::Address;
+use wallet::{Keypair, PublicKey};
+
+const KEYSTORE_VERSION: u8 = 1;
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 12;
+const ENCRYPTION_KEY_LENGTH: usize = 32;
+// Argon2id parameters recommended by OWASP: 19 MiB of memory, two passes, one lane.
+const DEFAULT_MEMORY_COST_KIB: u32 = 19 * 1024;
+const DEFAULT_TIME_COST: u32 = 2;
+const DEFAULT_PARALLELISM: u32 = 1;
+
+/// Error reading, writing or unlocking a keystore.
+#[derive(Debug)]
+pub enum KeystoreError {
+    ReadError(PathBuf, io::Error),
+    WriteError(PathBuf, io::Error),
+    ParseError(PathBuf, serde_json::Error),
+    UnsupportedVersion(u8),
+    AccountExists(Address),
+    UnknownAccount(Address),
+    WrongPassphrase,
+    KeyDerivationError(String),
+    CorruptedKey(Address),
+}
+
+impl Display for KeystoreError {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        match self {
+            KeystoreError::ReadError(path, error) => write!(fmt, "Can\'t read \"{}\": {}", path.display(), error),
+            KeystoreError::WriteError(path, error) => write!(fmt, "Can\'t write \"{}\": {}", path.display(), error),
+            KeystoreError::ParseError(path, error) => write!(fmt, "Can\'t parse \"{}\": {}", path.display(), error),
+            KeystoreError::UnsupportedVersion(version) => write!(fmt, "Unsupported keystore version: {}", version),
+            KeystoreError::AccountExists(address) => write!(fmt, "Account {} is already in the keystore", address),
+            KeystoreError::UnknownAccount(address) => write!(fmt, "Account {} is not in the keystore", address),
+            KeystoreError::WrongPassphrase => write!(fmt, "Wrong passphrase"),
+            KeystoreError::KeyDerivationError(error) => write!(fmt, "Can\'t derive encryption key: {}", error),
+            KeystoreError::CorruptedKey(address) => write!(fmt, "Key of account {} is corrupted", address),
+        }
+    }
+}
+
+/// Argon2id parameters an encryption key is derived with.
+#[derive(Clone, Copy, Deserialize, Serialize)]
+struct KdfParams {
+    memory_cost_kib: u32,
+    time_cost: u32,
+    parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> KdfParams {
+        KdfParams {
+            memory_cost_kib: DEFAULT_MEMORY_COST_KIB,
+            time_cost: DEFAULT_TIME_COST,
+            parallelism: DEFAULT_PARALLELISM,
+        }
+    }
+}
+
+impl KdfParams {
+    fn derive_key(&self, passphrase: &str, salt: &[u8]) ->
+        Result<Zeroizing<[u8; ENCRYPTION_KEY_LENGTH]>, KeystoreError> {
+        let params = match Params::new(self.memory_cost_kib, self.time_cost, self.parallelism,
+            Some(ENCRYPTION_KEY_LENGTH)) {
+            Ok(params) => params,
+            Err(error) => {
+                return Err(KeystoreError::KeyDerivationError(error.to_string()));
+            },
+        };
+        
+        let mut key = Zeroizing::new([0; ENCRYPTION_KEY_LENGTH]);
+        
+        match Argon2::new(Algorithm::Argon2id, Version::V0x13, params).hash_password_into(passphrase.as_bytes(), salt,
+            key.as_mut()) {
+            Ok(()) => Ok(key),
+            Err(error) => Err(KeystoreError::KeyDerivationError(error.to_string())),
+        }
+    }
+}
+
+/// Public part of an account stored in a keystore.
+#[derive(Clone, Serialize)]
+pub struct Account {
+    address: Address,
+    public_key: PublicKey,
+}
+
+impl Account {
+    /// Returns the address.
+    pub fn address(&self) -> Address {
+        self.address
+    }
+    
+    /// Returns the public key.
+    pub fn public_key(&self) -> PublicKey {
+        self.public_key
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+struct EncryptedKey {
+    address: Address,
+    public_key: PublicKey,
+    kdf: KdfParams,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl EncryptedKey {
+    fn account(&self) -> Account {
+        Account {
+            address: self.address,
+            public_key: self.public_key,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct KeystoreFile {
+    version: u8,
+    accounts: Vec<EncryptedKey>,
+}
+
+/// Keystore file; changes are kept in memory until it is saved.
+pub struct Keystore {
+    path: PathBuf,
+    keys: Vec<EncryptedKey>,
+}
+
+impl Keystore {
+    /// Opens the keystore at `path`; a missing file is an empty keystore.
+    pub fn open(path: &Path) -> Result<Keystore, KeystoreError> {
+        if !path.exists() {
+            return Ok(Keystore {
+                path: path.to_path_buf(),
+                keys: Vec::new(),
+            });
+        }
+        
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) => {
+                return Err(KeystoreError::ReadError(path.to_path_buf(), error));
+            },
+        };
+        
+        let keystore_file = match serde_json::from_str::<KeystoreFile>(contents.as_str()) {
+            Ok(keystore_file) => keystore_file,
+            Err(error) => {
+                return Err(KeystoreError::ParseError(path.to_path_buf(), error));
+            },
+        };
+        
+        if keystore_file.version != KEYSTORE_VERSION {
+            return Err(KeystoreError::UnsupportedVersion(keystore_file.version));
+        }
+        
+        Ok(Keystore {
+            path: path.to_path_buf(),
+            keys: keystore_file.accounts,
+        })
+    }
+    
+    /// Returns the path of the keystore file.
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+    
+    /// Lists the accounts in the order they were added.
+    pub fn accounts(&self) -> Vec<Account> {
+        self.keys.iter().map(EncryptedKey::account).collect()
+    }
+    
+    /// Tells whether the keystore holds the account at `address`.
+    pub fn contains(&self, address: &Address) -> bool {
+        self.keys.iter().any(|key| key.address == *address)
+    }
+    
+    /// Encrypts `keypair` with `passphrase` and adds it.
+    pub fn add(&mut self, keypair: &Keypair, passphrase: &str) -> Result<Account, KeystoreError> {
+        let address = keypair.address();
+        
+        if self.contains(&address) {
+            return Err(KeystoreError::AccountExists(address));
+        }
+        
+        let mut salt = [0; SALT_LENGTH];
+        let mut nonce = [0; NONCE_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+        
+        let kdf = KdfParams::default();
+        let key = kdf.derive_key(passphrase, &salt)?;
+        let secret_key = keypair.secret_bytes();
+        let payload = Payload {
+            msg: secret_key.as_slice(),
+            aad: address.as_bytes(),
+        };
+        
+        let ciphertext = ChaCha20Poly1305::new(Key::from_slice(key.as_slice()))
+            .encrypt(Nonce::from_slice(&nonce), payload)
+            .expect("Encryption of a secret key must succeed");
+        
+        let encrypted_key = EncryptedKey {
+            address,
+            public_key: keypair.public_key(),
+            kdf,
+            salt: hex::encode(salt),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        };
+        
+        let account = encrypted_key.account();
+        self.keys.push(encrypted_key);
+        
+        Ok(account)
+    }
+    
+    /// Decrypts the keypair of the account at `address`.
+    pub fn unlock(&self, address: &Address, passphrase: &str) -> Result<Keypair, KeystoreError> {
+        let encrypted_key = match self.keys.iter().find(|key| key.address == *address) {
+            Some(encrypted_key) => encrypted_key,
+            None => {
+                return Err(KeystoreError::UnknownAccount(*address));
+            },
+        };
+        
+        let (salt, nonce, ciphertext) = match (hex::decode(&encrypted_key.salt), hex::decode(&encrypted_key.nonce),
+            hex::decode(&encrypted_key.ciphertext)) {
+            (Ok(salt), Ok(nonce), Ok(ciphertext)) if nonce.len() == NONCE_LENGTH => (salt, nonce, ciphertext),
+            _ => {
+                return Err(KeystoreError::CorruptedKey(*address));
+            },
+        };
+        
+        let key = encrypted_key.kdf.derive_key(passphrase, &salt)?;
+        let payload = Payload {
+            msg: ciphertext.as_slice(),
+            aad: address.as_bytes(),
+        };
+        
+        let secret_key = match ChaCha20Poly1305::new(Key::from_slice(key.as_slice()))
+            .decrypt(Nonce::from_slice(&nonce), payload) {
+            Ok(secret_key) => Zeroizing::new(secret_key),
+            Err(_) => {
+                return Err(KeystoreError::WrongPassphrase);
+            },
+        };
+        
+        let keypair = match <[u8; SECRET_KEY_LENGTH]>::try_from(secret_key.as_slice()) {
+            Ok(secret_key) => Keypair::from_secret_bytes(&Zeroizing::new(secret_key)),
+            Err(_) => {
+                return Err(KeystoreError::CorruptedKey(*address));
+            },
+        };
+        
+        if keypair.address() != *address {
+            return Err(KeystoreError::CorruptedKey(*address));
+        }
+        
+        Ok(keypair)
+    }
+    
+    /// Writes the keystore, replacing the file atomically and keeping it readable by the owner only.
+    pub fn save(&self) -> Result<(), KeystoreError> {
+        let keystore_file = KeystoreFile {
+            version: KEYSTORE_VERSION,
+            accounts: self.keys.clone(),
+        };
+        
+        let contents = serde_json::to_string_pretty(&keystore_file).expect("Keystore must be serializable");
+        let temporary_path = self.path.with_extension("tmp");
+        
+        let result = Keystore::create_private(&temporary_path)
+            .and_then(|mut file| file.write_all(contents.as_bytes()).and_then(|_| file.sync_all()))
+            .and_then(|_| fs::rename(&temporary_path, &self.path));
+        
+        match result {
+            Ok(()) => Ok(()),
+            Err(error) => {
+                let _ = fs::remove_file(&temporary_path);
+                Err(KeystoreError::WriteError(self.path.clone(), error))
+            },
+        }
+    }
+    
+    #[cfg(unix)]
+    fn create_private(path: &Path) -> io::Result<fs::File> {
+        use std::os::unix::fs::OpenOptionsExt;
+        
+        OpenOptions::new().write(true).create(true).truncate(true).mode(0o600).open(path)
+    }
+    
+    #[cfg(not(unix))]
+    fn create_private(path: &Path) -> io::Result<fs::File> {
+        OpenOptions::new().write(true).create(true).truncate(true).open(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const PASSPHRASE: &str = "correct horse battery staple";
+    
+    // Keystore file in a directory of its own, removed when dropped.
+    struct TemporaryKeystore {
+        directory: PathBuf,
+    }
+    
+    impl TemporaryKeystore {
+        fn new(name: &str) -> TemporaryKeystore {
+            let directory = std::env::temp_dir().join(format!("simple-blockchain-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&directory);
+            fs::create_dir_all(&directory).unwrap();
+            
+            TemporaryKeystore {
+                directory,
+            }
+        }
+        
+        fn path(&self) -> PathBuf {
+            self.directory.join("keystore.json")
+        }
+    }
+    
+    impl Drop for TemporaryKeystore {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.directory);
+        }
+    }
+    
+    fn saved_keystore(temporary_keystore: &TemporaryKeystore) -> (Keypair, Address) {
+        let keypair = Keypair::generate();
+        let mut keystore = Keystore::open(&temporary_keystore.path()).unwrap();
+        let address = keystore.add(&keypair, PASSPHRASE).unwrap().address();
+        keystore.save().unwrap();
+        
+        (keypair, address)
+    }
+    
+    #[test]
+    fn missing_file_is_empty_keystore() {
+        let temporary_keystore = TemporaryKeystore::new("missing");
+        
+        assert!(Keystore::open(&temporary_keystore.path()).unwrap().accounts().is_empty());
+    }
+    
+    #[test]
+    fn saved_key_unlocks() {
+        let temporary_keystore = TemporaryKeystore::new("unlock");
+        let (keypair, address) = saved_keystore(&temporary_keystore);
+        let keystore = Keystore::open(&temporary_keystore.path()).unwrap();
+        
+        assert_eq!(keystore.accounts().len(), 1);
+        assert_eq!(keystore.accounts()[0].public_key(), keypair.public_key());
+        assert_eq!(*keystore.unlock(&address, PASSPHRASE).unwrap().secret_bytes(), *keypair.secret_bytes());
+        assert!(matches!(Keystore::open(&temporary_keystore.path()).unwrap().add(&keypair, PASSPHRASE),
+            Err(KeystoreError::AccountExists(_))));
+    }
+    
+    #[cfg(unix)]
+    #[test]
+    fn saved_file_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+        
+        let temporary_keystore = TemporaryKeystore::new("private");
+        saved_keystore(&temporary_keystore);
+        
+        let mode = fs::metadata(temporary_keystore.path()).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+    
+    #[test]
+    fn wrong_passphrase_is_rejected() {
+        let temporary_keystore = TemporaryKeystore::new("passphrase");
+        let (_, address) = saved_keystore(&temporary_keystore);
+        let keystore = Keystore::open(&temporary_keystore.path()).unwrap();
+        
+        assert!(matches!(keystore.unlock(&address, "wrong passphrase"), Err(KeystoreError::WrongPassphrase)));
+        assert!(matches!(keystore.unlock(&Address::new([1; 20]), PASSPHRASE), Err(KeystoreError::UnknownAccount(_))));
+    }
+    
+    #[test]
+    fn tampered_ciphertext_is_rejected() {
+        let temporary_keystore = TemporaryKeystore::new("tampered");
+        let (_, address) = saved_keystore(&temporary_keystore);
+        let mut keystore = Keystore::open(&temporary_keystore.path()).unwrap();
+        
+        let mut ciphertext = hex::decode(&keystore.keys[0].ciphertext).unwrap();
+        ciphertext[0] ^= 1;
+        keystore.keys[0].ciphertext = hex::encode(ciphertext);
+        
+        assert!(matches!(keystore.unlock(&address, PASSPHRASE), Err(KeystoreError::WrongPassphrase)));
+        
+        keystore.keys[0].ciphertext = String::from("not hex");
+        
+        assert!(matches!(keystore.unlock(&address, PASSPHRASE), Err(KeystoreError::CorruptedKey(_))));
+    }
+    
+    #[test]
+    fn unsupported_version_is_rejected() {
+        let temporary_keystore = TemporaryKeystore::new("version");
+        fs::write(temporary_keystore.path(), r#"{"version": 2, "accounts": []}"#).unwrap();
+        
+        assert!(matches!(Keystore::open(&temporary_keystore.path()), Err(KeystoreError::UnsupportedVersion(2))));
+    }
+}
@@ -0,0 +1,46 @@
+//! Core of a simple proof-of-work blockchain: blocks, chains, transactions and the consensus rules they are
+//! validated against.
+//!
+//! The node server is built on top of this crate; tools that only need to inspect or validate chains can depend
+//! on it without the server by disabling the default `server` feature. The `api` feature adds the request and
+//! response bodies of the node's HTTP API, the `client` feature a typed async client of it and the `wallet`
+//! feature keypairs, signed transactions and an encrypted keystore.
+//!
+//! ```
+//! use simple_blockchain::{block::Block, chain::Chain, clock::{Clock, SystemClock}, rules::ValidationRules};
+//!
+//! let clock = SystemClock;
+//! let rules = ValidationRules::default();
+//! let chain = Chain::new(&Block::genesis(clock.now()));
+//!
+//! assert!(chain.validate(&rules, clock.now()).is_ok());
+//! ```
+
+pub mod time;
+pub mod clock;
+mod hex_string;
+pub mod hash;
+pub mod address;
+pub mod proof;
+pub mod reward;
+pub mod rules;
+pub mod transaction;
+pub mod transactions;
+pub mod block;
+pub mod canonical;
+pub mod chain;
+pub mod encoding;
+#[cfg(feature = "api")]
+pub mod net_location;
+#[cfg(feature = "api")]
+pub mod node;
+#[cfg(feature = "api")]
+pub mod api;
+#[cfg(feature = "client")]
+pub mod client;
+#[cfg(feature = "wallet")]
+pub mod wallet;
+#[cfg(feature = "wallet")]
+pub mod keystore;
+#[cfg(test)]
+mod fixtures;
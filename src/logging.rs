@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+use clap::ValueEnum;
+use tracing_subscriber::EnvFilter;
+
+const DEFAULT_FILTER: &str = "info";
+
+#[derive(Clone, Copy, Default, Deserialize, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct LoggingSettings {
+    #[serde(default = "default_filter")]
+    filter: String,
+    #[serde(default)]
+    format: LogFormat,
+}
+
+fn default_filter() -> String {
+    String::from(DEFAULT_FILTER)
+}
+
+impl Default for LoggingSettings {
+    fn default() -> LoggingSettings {
+        LoggingSettings {
+            filter: default_filter(),
+            format: LogFormat::default(),
+        }
+    }
+}
+
+impl LoggingSettings {
+    pub fn filter(&self) -> &str {
+        self.filter.as_str()
+    }
+    
+    pub fn format(&self) -> LogFormat {
+        self.format
+    }
+    
+    pub fn set_filter(&mut self, filter: &str) {
+        self.filter = String::from(filter);
+    }
+    
+    pub fn set_format(&mut self, format: LogFormat) {
+        self.format = format;
+    }
+    
+    pub fn validate(&self) -> Vec<String> {
+        match EnvFilter::try_new(self.filter()) {
+            Ok(_) => Vec::new(),
+            Err(error) => vec![format!("logging.filter is invalid: {}", error)],
+        }
+    }
+}
+
+pub fn initialize(settings: &LoggingSettings) {
+    // The filter comes from the settings only, so `--log` and `BLOCKCHAIN_LOG` keep their precedence.
+    let subscriber = tracing_subscriber::fmt().with_env_filter(EnvFilter::new(settings.filter()));
+    
+    match settings.format() {
+        LogFormat::Text => subscriber.init(),
+        LogFormat::Json => subscriber.json().with_current_span(true).with_span_list(true).init(),
+    }
+}
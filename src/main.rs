@@ -1,36 +1,133 @@
-mod time;
-mod net_location;
-mod proof;
+mod logging;
+mod metrics;
+mod arguments;
 mod settings;
-mod node;
 mod nodes;
-mod transaction;
-mod transactions;
-mod block;
-mod chain;
 mod requests;
 mod blockchain;
+mod state;
+mod storage;
 mod routing;
 
 use std::io::Result;
-use std::sync::{Arc, Mutex};
-use actix_web::{App, HttpServer};
+use std::process;
+use std::sync::Arc;
+use std::time::Instant;
+use actix_web::{web, App, HttpServer, dev::Service};
+use clap::Parser;
+use tracing::{info, warn, error, info_span, field, Instrument};
 
+use simple_blockchain::clock;
+
+use arguments::Arguments;
 use settings::Settings;
+use clock::SystemClock;
+use metrics::Metrics;
 use blockchain::Blockchain;
+use state::State;
 
 #[actix_rt::main]
 async fn main() -> Result<()> {
-    let settings = Settings::new();
-    let blockchain = Blockchain::new(settings.socket_address());
+    let arguments = Arguments::parse();
+    let settings = match Settings::load(&arguments) {
+        Ok(settings) => settings,
+        Err(errors) => {
+            for error in errors {
+                eprintln!("Invalid settings: {}", error);
+            }
+            
+            process::exit(1);
+        },
+    };
+    
+    if arguments.print_config() {
+        println!("{}", settings.to_json());
+        return Ok(());
+    }
+    
+    logging::initialize(settings.logging());
+    
+    let metrics = Arc::new(Metrics::new());
+    let mut blockchain = Blockchain::new(settings.network_id(), settings.local_addresses(), settings.payout_address(),
+        settings.validation_rules(), Arc::new(SystemClock), metrics.clone());
+    
+    match storage::load_chain(settings.data_dir(), blockchain.rules(), blockchain.clock().now()) {
+        Ok(Some(chain)) => {
+            info!(num_of_blocks = chain.num_of_blocks(), "Chain loaded");
+            blockchain.restore_chain(chain);
+        },
+        Ok(None) => {
+        },
+        Err(error) => {
+            error!(%error, "Chain loading failed");
+            process::exit(1);
+        },
+    }
     
-    println!("Net location: {}", blockchain.net_location());
+    info!(network_id = blockchain.network_id(), net_location = blockchain.net_location(), "Node started");
     
-    let blockchain = Arc::new(Mutex::new(blockchain));
+    let state = State::start(blockchain);
+    
+    if !settings.peers().is_empty() {
+        match state.register_nodes(settings.peers().to_vec()).await {
+            Ok(Ok(nodes)) => info!(num_of_nodes = nodes.len(), "Peers registered"),
+            Ok(Err(error)) => warn!(%error, "Peers registration failed"),
+            Err(error) => warn!(%error, "Peers registration failed"),
+        }
+    }
+    
+    if settings.mine() {
+        let state = state.clone();
+        
+        actix_rt::spawn(async move {
+            loop {
+                match state.mine(None).await {
+                    Ok(Ok(_)) => {
+                    },
+                    Ok(Err(error)) => {
+                        warn!(%error, "Mining stopped");
+                        break;
+                    },
+                    Err(error) => {
+                        warn!(%error, "Mining stopped");
+                        break;
+                    },
+                }
+            }
+        });
+    }
+    let app_state = state.clone();
     let http_server = HttpServer::new(move || {
+            let metrics = metrics.clone();
+            
             App::new()
-            .data(blockchain.clone())
+            .data(app_state.clone())
+            .wrap_fn(move |request, service| {
+                let metrics = metrics.clone();
+                let method = request.method().to_string();
+                let route = request.match_pattern().unwrap_or_else(|| String::from("unmatched"));
+                let span = info_span!("request", method = method.as_str(), route = route.as_str(),
+                    block_index = field::Empty);
+                let started_at = Instant::now();
+                let response = span.in_scope(|| service.call(request));
+                
+                async move {
+                    let response = response.await?;
+                    let status = response.status().as_u16();
+                    metrics.observe_request(method.as_str(), route.as_str(), status, started_at.elapsed());
+                    info!(status, elapsed_ms = started_at.elapsed().as_millis() as u64, "Request completed");
+                    Ok(response)
+                }.instrument(span)
+            })
             .configure(routing::initialize)
+            .default_service(web::route().to(routing::not_found))
         });
-    http_server.bind(settings.socket_address())?.run().await
+    http_server.bind(settings.bind_address())?.run().await?;
+    
+    match storage::save_chain(settings.data_dir(), state.snapshot().chain()) {
+        Ok(()) => info!(data_dir = %settings.data_dir().display(), "Chain saved"),
+        Err(error) => warn!(%error, "Chain saving failed"),
+    }
+    
+    Ok(())
 }
@@ -0,0 +1,131 @@
+use std::time::Duration;
+use prometheus::{Encoder, TextEncoder, Registry, IntCounter, IntCounterVec, IntGauge, Gauge, Histogram, HistogramVec,
+    HistogramOpts, Opts};
+
+use crate::blockchain;
+
+use blockchain::Blockchain;
+
+pub const CONTENT_TYPE: &str = prometheus::TEXT_FORMAT;
+
+const NAMESPACE: &str = "blockchain";
+
+pub struct Metrics {
+    registry: Registry,
+    chain_height: IntGauge,
+    blocks_mined: IntCounter,
+    reorgs: IntCounter,
+    reorg_depth: Histogram,
+    mempool_size: IntGauge,
+    peer_count: IntGauge,
+    peer_failures: IntCounterVec,
+    hash_rate: Gauge,
+    requests: IntCounterVec,
+    request_duration: HistogramVec,
+    resolve_conflicts_duration: Histogram,
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        let registry = Registry::new();
+        
+        let chain_height = IntGauge::with_opts(Metrics::opts("chain_height", "Number of blocks in the chain"))
+            .expect("Metric must be valid");
+        let blocks_mined = IntCounter::with_opts(Metrics::opts("blocks_mined_total", "Blocks mined by this node"))
+            .expect("Metric must be valid");
+        let reorgs = IntCounter::with_opts(Metrics::opts("reorgs_total", "Chain reorganizations"))
+            .expect("Metric must be valid");
+        let reorg_depth = Histogram::with_opts(HistogramOpts::from(
+            Metrics::opts("reorg_depth_blocks", "Blocks dropped from the chain by a reorganization"))
+            .buckets(vec![1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0, 128.0]))
+            .expect("Metric must be valid");
+        let mempool_size = IntGauge::with_opts(Metrics::opts("mempool_size", "Transactions waiting to be mined"))
+            .expect("Metric must be valid");
+        let peer_count = IntGauge::with_opts(Metrics::opts("peer_count", "Registered peer nodes"))
+            .expect("Metric must be valid");
+        let peer_failures = IntCounterVec::new(Metrics::opts("peer_failures_total",
+            "Failed chain downloads per peer"), &["peer"])
+            .expect("Metric must be valid");
+        let hash_rate = Gauge::with_opts(Metrics::opts("pow_hash_rate",
+            "Proof-of-work hashes per second during the last mining"))
+            .expect("Metric must be valid");
+        let requests = IntCounterVec::new(Metrics::opts("http_requests_total", "HTTP requests per route"),
+            &["method", "route", "status"])
+            .expect("Metric must be valid");
+        let request_duration = HistogramVec::new(HistogramOpts::from(
+            Metrics::opts("http_request_duration_seconds", "HTTP request latency per route")),
+            &["method", "route"])
+            .expect("Metric must be valid");
+        let resolve_conflicts_duration = Histogram::with_opts(HistogramOpts::from(
+            Metrics::opts("resolve_conflicts_duration_seconds", "Duration of conflict resolution"))
+            .buckets(vec![0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0]))
+            .expect("Metric must be valid");
+        
+        registry.register(Box::new(chain_height.clone())).expect("Metric must be registered once");
+        registry.register(Box::new(blocks_mined.clone())).expect("Metric must be registered once");
+        registry.register(Box::new(reorgs.clone())).expect("Metric must be registered once");
+        registry.register(Box::new(reorg_depth.clone())).expect("Metric must be registered once");
+        registry.register(Box::new(mempool_size.clone())).expect("Metric must be registered once");
+        registry.register(Box::new(peer_count.clone())).expect("Metric must be registered once");
+        registry.register(Box::new(peer_failures.clone())).expect("Metric must be registered once");
+        registry.register(Box::new(hash_rate.clone())).expect("Metric must be registered once");
+        registry.register(Box::new(requests.clone())).expect("Metric must be registered once");
+        registry.register(Box::new(request_duration.clone())).expect("Metric must be registered once");
+        registry.register(Box::new(resolve_conflicts_duration.clone())).expect("Metric must be registered once");
+        
+        Metrics {
+            registry,
+            chain_height,
+            blocks_mined,
+            reorgs,
+            reorg_depth,
+            mempool_size,
+            peer_count,
+            peer_failures,
+            hash_rate,
+            requests,
+            request_duration,
+            resolve_conflicts_duration,
+        }
+    }
+    
+    fn opts(name: &str, help: &str) -> Opts {
+        Opts::new(name, help).namespace(NAMESPACE)
+    }
+    
+    pub fn observe_mining(&self, num_of_hashes: u64, duration: Duration) {
+        self.blocks_mined.inc();
+        
+        if duration.as_secs_f64() > 0.0 {
+            self.hash_rate.set(num_of_hashes as f64 / duration.as_secs_f64());
+        }
+    }
+    
+    pub fn observe_reorg(&self, depth: usize) {
+        self.reorgs.inc();
+        self.reorg_depth.observe(depth as f64);
+    }
+    
+    pub fn observe_peer_failure(&self, net_location: &str) {
+        self.peer_failures.with_label_values(&[net_location]).inc();
+    }
+    
+    pub fn observe_resolve_conflicts(&self, duration: Duration) {
+        self.resolve_conflicts_duration.observe(duration.as_secs_f64());
+    }
+    
+    pub fn observe_request(&self, method: &str, route: &str, status: u16, duration: Duration) {
+        self.requests.with_label_values(&[method, route, status.to_string().as_str()]).inc();
+        self.request_duration.with_label_values(&[method, route]).observe(duration.as_secs_f64());
+    }
+    
+    pub fn render(&self, blockchain: &Blockchain) -> String {
+        self.chain_height.set(blockchain.chain().num_of_blocks() as i64);
+        self.mempool_size.set(blockchain.current_transactions().len() as i64);
+        self.peer_count.set(blockchain.nodes().len() as i64);
+        
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer).expect("Metrics must be encodable");
+        String::from_utf8(buffer).expect("Metrics must be valid UTF-8")
+    }
+}
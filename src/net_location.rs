@@ -1,8 +1,216 @@
-use url::{Url, ParseError};
+//! Canonical net locations of nodes.
 
+use std::collections::HashSet;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use url::{Url, Host, ParseError};
+
+const DEFAULT_SCHEME: &str = "http";
+const LOCALHOST: &str = "localhost";
+
+/// Net locations a node is reachable at, used to recognize the node itself among peers.
+#[derive(Clone)]
+pub struct LocalAddresses {
+    public_net_location: String,
+    net_locations: HashSet<String>,
+    public_socket_addresses: Vec<SocketAddr>,
+    bind_socket_addresses: Vec<SocketAddr>,
+}
+
+impl LocalAddresses {
+    /// Collects the net locations of a node listening on `bind_address` and advertised as `public_address`.
+    ///
+    /// A node listening on a loopback or unspecified address is also reachable as localhost. The advertised and
+    /// listening host names are resolved once, here.
+    pub fn new(bind_address: &str, public_address: Option<&str>) -> Result<LocalAddresses, ParseError> {
+        let bind_url = parse_url(bind_address)?;
+        let bind_net_location = net_location(&bind_url)?;
+        let public_net_location = match public_address {
+            Some(public_address) => net_location_by_url(public_address)?,
+            None => bind_net_location.clone(),
+        };
+        
+        let public_socket_addresses = resolve(public_net_location.as_str()).unwrap_or_default();
+        let bind_socket_addresses = resolve(bind_net_location.as_str()).unwrap_or_default();
+        let mut net_locations = HashSet::from([bind_net_location, public_net_location.clone()]);
+        
+        if is_local_host(&bind_url) {
+            let port = bind_url.port_or_known_default().unwrap_or_default();
+            
+            net_locations.insert(format!("{}:{}", Ipv4Addr::LOCALHOST, port));
+            net_locations.insert(format!("[{}]:{}", Ipv6Addr::LOCALHOST, port));
+            net_locations.insert(format!("{}:{}", LOCALHOST, port));
+        }
+        
+        Ok(LocalAddresses {
+            public_net_location,
+            net_locations,
+            public_socket_addresses,
+            bind_socket_addresses,
+        })
+    }
+    
+    /// Returns the net location the node advertises.
+    pub fn public_net_location(&self) -> &str {
+        self.public_net_location.as_str()
+    }
+    
+    /// Tells whether `net_location` is one of the node's known net locations. Nothing is resolved.
+    pub fn contains(&self, net_location: &str) -> bool {
+        self.net_locations.contains(net_location)
+    }
+    
+    /// Tells whether `net_location` refers to the node itself.
+    ///
+    /// Besides the known net locations, a host name is resolved and its addresses compared to the advertised and
+    /// listening ones, so the node is also recognized by its DNS name or, when listening on all interfaces, by any
+    /// address of this machine. A host that can't be resolved isn't the node. Resolving blocks, so this must not
+    /// run on an async executor.
+    pub fn resolves_to_node(&self, net_location: &str) -> bool {
+        if self.contains(net_location) {
+            return true;
+        }
+        
+        let socket_addresses = match resolve(net_location) {
+            Ok(socket_addresses) => socket_addresses,
+            Err(_) => {
+                return false;
+            },
+        };
+        
+        socket_addresses.iter().any(|socket_address| self.public_socket_addresses.contains(socket_address)
+            || self.bind_socket_addresses.iter().any(|bind_address| is_bound_to(*bind_address, *socket_address)))
+    }
+}
+
+/// Parses a url, defaulting to http when it has no scheme.
+pub fn parse_url(url: &str) -> Result<Url, ParseError> {
+    if url.contains("://") {
+        Url::parse(url)
+    } else {
+        Url::parse(format!("{}://{}", DEFAULT_SCHEME, url).as_str())
+    }
+}
+
+/// Returns the canonical `host:port` of a url: lowercase host names without the trailing dot, bracketed IPv6
+/// addresses, IPv4-mapped IPv6 addresses as IPv4 and the scheme's default port when none is given.
 pub fn net_location_by_url(url: &str) -> Result<String, ParseError> {
-    let parsed_url = Url::parse(url)?;
-    let net_location = format!("{}:{}", parsed_url.host_str().unwrap_or_default(),
-        parsed_url.port().unwrap_or_default());
-    Ok(net_location)
+    net_location(&parse_url(url)?)
+}
+
+/// Returns the scheme and canonical net location of a url.
+pub fn base_url(url: &Url) -> Result<String, ParseError> {
+    Ok(format!("{}://{}", url.scheme(), net_location(url)?))
+}
+
+fn net_location(url: &Url) -> Result<String, ParseError> {
+    let host = match url.host() {
+        Some(host) => canonical_host(host),
+        None => {
+            return Err(ParseError::EmptyHost);
+        },
+    };
+    
+    match url.port_or_known_default() {
+        Some(port) => Ok(format!("{}:{}", host, port)),
+        None => Err(ParseError::InvalidPort),
+    }
+}
+
+fn canonical_host(host: Host<&str>) -> String {
+    match host {
+        Host::Domain(domain) => domain.trim_end_matches('.').to_lowercase(),
+        Host::Ipv4(address) => address.to_string(),
+        Host::Ipv6(address) => match address.to_ipv4_mapped() {
+            Some(address) => address.to_string(),
+            None => format!("[{}]", address),
+        },
+    }
+}
+
+fn resolve(net_location: &str) -> io::Result<Vec<SocketAddr>> {
+    net_location.to_socket_addrs().map(|addresses| addresses.map(canonical_socket_address).collect())
+}
+
+fn canonical_socket_address(socket_address: SocketAddr) -> SocketAddr {
+    SocketAddr::new(socket_address.ip().to_canonical(), socket_address.port())
+}
+
+// Tells whether a socket listening on `bind_address` accepts connections to `socket_address`.
+fn is_bound_to(bind_address: SocketAddr, socket_address: SocketAddr) -> bool {
+    if bind_address.port() != socket_address.port() {
+        return false;
+    }
+    
+    if bind_address.ip().is_unspecified() {
+        is_local_ip(socket_address.ip())
+    } else {
+        bind_address.ip().to_canonical() == socket_address.ip()
+    }
+}
+
+// Only addresses of this machine's interfaces can be bound to.
+fn is_local_ip(address: IpAddr) -> bool {
+    address.is_loopback() || address.is_unspecified() || UdpSocket::bind(SocketAddr::new(address, 0)).is_ok()
+}
+
+fn is_local_host(url: &Url) -> bool {
+    match url.host() {
+        Some(Host::Domain(domain)) => domain.trim_end_matches('.').eq_ignore_ascii_case(LOCALHOST),
+        Some(Host::Ipv4(address)) => address.is_loopback() || address.is_unspecified(),
+        Some(Host::Ipv6(address)) => address.is_loopback() || address.is_unspecified(),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn known_net_locations_are_local() {
+        let local_addresses = LocalAddresses::new("127.0.0.1:8080", Some("http://Node.Example.com.:8080")).unwrap();
+        
+        assert_eq!(local_addresses.public_net_location(), "node.example.com:8080");
+        assert!(local_addresses.contains("node.example.com:8080"));
+        assert!(local_addresses.contains("localhost:8080"));
+        assert!(local_addresses.contains("[::1]:8080"));
+        assert!(!local_addresses.contains("127.0.0.1:8081"));
+    }
+    
+    #[test]
+    fn specific_bind_address_only_matches_itself() {
+        let local_addresses = LocalAddresses::new("127.0.0.1:8080", None).unwrap();
+        
+        assert!(!local_addresses.resolves_to_node("127.0.0.2:8080"));
+        assert!(!local_addresses.resolves_to_node("192.0.2.1:8080"));
+    }
+    
+    #[test]
+    fn unspecified_bind_address_matches_any_local_address() {
+        let local_addresses = LocalAddresses::new("0.0.0.0:8080", Some("http://192.0.2.1:8080")).unwrap();
+        
+        assert!(local_addresses.resolves_to_node("127.0.0.2:8080"));
+        assert!(!local_addresses.contains("127.0.0.2:8080"));
+        assert!(!local_addresses.resolves_to_node("198.51.100.1:8080"));
+        assert!(!local_addresses.resolves_to_node("127.0.0.2:8081"));
+        assert!(!local_addresses.resolves_to_node("unresolvable.invalid:8080"));
+        
+        // Connecting a datagram socket sends nothing but picks the interface address a peer would be given.
+        let lan_address = UdpSocket::bind("0.0.0.0:0")
+            .and_then(|socket| socket.connect("198.51.100.1:9").map(|()| socket))
+            .and_then(|socket| socket.local_addr());
+        
+        if let Ok(lan_address) = lan_address {
+            assert!(local_addresses.resolves_to_node(format!("{}:8080", lan_address.ip()).as_str()));
+        }
+    }
+    
+    #[test]
+    fn public_host_name_is_resolved() {
+        let local_addresses = LocalAddresses::new("0.0.0.0:8080", Some("http://localhost:9090")).unwrap();
+        
+        assert!(local_addresses.resolves_to_node("127.0.0.1:9090"));
+        assert!(!local_addresses.resolves_to_node("127.0.0.1:9091"));
+    }
 }
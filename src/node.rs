@@ -1,20 +1,39 @@
-use serde::Serialize;
+//! Peer nodes.
 
-#[derive(Eq, Clone, Hash, Serialize)]
+use std::borrow::Borrow;
+use std::hash::{Hash, Hasher};
+use url::ParseError;
+use serde::{Deserialize, Serialize};
+
+use crate::net_location;
+
+/// Peer node, identified by its canonical net location.
+#[derive(Eq, Clone, Deserialize, Serialize)]
 pub struct Node {
     net_location: String,
+    url: String,
 }
 
 impl Node {
-    pub fn new(net_location: &str) -> Node {
-        Node {
-            net_location: String::from(net_location),
-        }
+    /// Parses a node url, defaulting to http and the scheme's default port.
+    pub fn from_url(url: &str) -> Result<Node, ParseError> {
+        let url = net_location::parse_url(url)?;
+        
+        Ok(Node {
+            net_location: net_location::net_location_by_url(url.as_str())?,
+            url: net_location::base_url(&url)?,
+        })
     }
     
+    /// Returns the canonical `host:port` identifying the node.
     pub fn net_location(&self) -> &str {
         self.net_location.as_str()
     }
+    
+    /// Returns the base url requests to the node are sent to.
+    pub fn url(&self) -> &str {
+        self.url.as_str()
+    }
 }
 
 impl PartialEq for Node {
@@ -22,3 +41,15 @@ impl PartialEq for Node {
         self.net_location == other.net_location
     }
 }
+
+impl Hash for Node {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.net_location.hash(state);
+    }
+}
+
+impl Borrow<str> for Node {
+    fn borrow(&self) -> &str {
+        self.net_location.as_str()
+    }
+}
@@ -3,7 +3,7 @@ use std::fmt::{Display, Formatter, Error};
 use url::ParseError;
 use serde::Serialize;
 
-use crate::{net_location, node};
+use simple_blockchain::node;
 
 use node::Node;
 
@@ -12,7 +12,7 @@ pub enum RegisterError {
     ParseError(ParseError),
     EqualsToTheCurrentNode,
     HasBeenRegistered,
-    BlockchainLocked,
+    NotRegistered,
 }
 
 impl Display for RegisterError {
@@ -22,7 +22,7 @@ impl Display for RegisterError {
             RegisterError::EqualsToTheCurrentNode => write!(fmt,
                 "Net location equals to the current node\'s net location"),
             RegisterError::HasBeenRegistered => write!(fmt, "Node has been already registered"),
-            RegisterError::BlockchainLocked => write!(fmt, "Blockchain locked"),
+            RegisterError::NotRegistered => write!(fmt, "Node has not been registered"),
         }
     }
 }
@@ -39,25 +39,32 @@ impl Nodes {
         }
     }
     
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+    
     pub fn iter(&self) -> Iter<'_, Node> {
         self.nodes.iter()
     }
     
-    pub fn register(&mut self, url: &str) -> Result<&Node, RegisterError> {
-        let net_location = match net_location::net_location_by_url(url) {
-            Ok(net_location) => net_location,
-            Err(error) => {
-                return Err(RegisterError::ParseError(error));
-            },
-        };
-        
-        let node = Node::new(net_location.as_str());
-        let node_to_return = node.clone();
+    pub fn contains(&self, net_location: &str) -> bool {
+        self.nodes.contains(net_location)
+    }
+    
+    pub fn register(&mut self, node: Node) -> Result<&Node, RegisterError> {
+        let net_location = String::from(node.net_location());
         
         if !self.nodes.insert(node) {
             return Err(RegisterError::HasBeenRegistered);
         }
         
-        Ok(self.nodes.get(&node_to_return).expect("Nodes must contain at least one item"))
+        Ok(self.nodes.get(net_location.as_str()).expect("Nodes must contain at least one item"))
+    }
+    
+    pub fn remove(&mut self, net_location: &str) -> Result<Node, RegisterError> {
+        match self.nodes.take(net_location) {
+            Some(node) => Ok(node),
+            None => Err(RegisterError::NotRegistered),
+        }
     }
 }
@@ -1,7 +1,10 @@
+//! Proof of work.
+
 use sha256::digest;
 
 const STRING_TO_VALIDATE: &str = "0000";
 
+/// Searches for the smallest proof that is valid after `previous_proof`.
 pub fn proof_of_work(previous_proof: i64) -> i64 {
     let mut proof: i64 = 0;
     
@@ -12,6 +15,7 @@ pub fn proof_of_work(previous_proof: i64) -> i64 {
     proof
 }
 
+/// Checks that the hash of `previous_proof` and `proof` ends with the required number of zeros.
 pub fn proof_is_valid(proof: i64, previous_proof: i64) -> bool {
     let string = format!("{}|{}", previous_proof, proof);
     let hash_string = digest(string);
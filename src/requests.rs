@@ -1,14 +1,24 @@
 use std::fmt::{Display, Formatter, Error};
-use reqwest;
+use std::sync::OnceLock;
+use futures::StreamExt;
+use tokio::runtime::{self, Runtime};
+use tracing::{Instrument, Span};
 
-use crate::chain;
+use simple_blockchain::{time, rules, block, chain, client};
 
-use chain::Chain;
+use time::Time;
+use rules::ValidationRules;
+use chain::{Chain, ValidationError};
+use client::{Client, ClientError};
+
+// reqwest needs a Tokio 1 reactor, which the actix-web runtime doesn't provide, so peers are queried on this one.
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
 
 #[derive(Debug)]
 pub enum RequestError {
     RequestError(String),
     ParseError(String),
+    InvalidChain(String, ValidationError),
 }
 
 impl Display for RequestError {
@@ -16,28 +26,111 @@ impl Display for RequestError {
         match self {
             RequestError::RequestError(url) => write!(fmt, "Can\'t get response from: {}", url),
             RequestError::ParseError(url) => write!(fmt, "Can\'t parse response from: {}", url),
+            RequestError::InvalidChain(url, error) => write!(fmt, "Invalid chain received from: {}: {}", url, error),
         }
     }
 }
 
-pub async fn load_chain(net_location: &str) -> Result<Chain, RequestError> {
-    let url = format!("http://{}/chain", net_location);
-    let url_for_error = url.clone();
-    let body = reqwest::get(url).await;
+impl From<ClientError> for RequestError {
+    fn from(error: ClientError) -> RequestError {
+        match error {
+            ClientError::DecodeError(url, _) => RequestError::ParseError(url),
+            ClientError::InvalidUrl(url)
+                | ClientError::RequestError(url, _)
+                | ClientError::Timeout(url)
+                | ClientError::ApiError(url, _, _)
+                | ClientError::UnexpectedStatus(url, _) => RequestError::RequestError(url),
+        }
+    }
+}
+
+pub async fn load_longer_chain(node_url: &str, chain: Chain, rules: &ValidationRules, now: Time) ->
+    Result<Option<Chain>, RequestError> {
+    let client = Client::new(node_url)?;
+    let rules = *rules;
+    let request = async move { longer_chain(&client, &chain, &rules, now).await }.instrument(Span::current());
     
-    match body {
-        Ok(body) => {
-            let chain = body.json::<Chain>().await;
-            
-            match chain {
-                Ok(chain) => Ok(chain),
-                Err(_) => {
-                    return Err(RequestError::ParseError(url_for_error));
-                },
-            }
-        },
-        Err(_) => {
-            return Err(RequestError::RequestError(url_for_error));
+    match runtime().spawn(request).await {
+        Ok(result) => result,
+        Err(_) => Err(RequestError::RequestError(String::from(node_url))),
+    }
+}
+
+// Only the blocks after the last block both chains share are downloaded; each one is validated as it arrives, so
+// the download stops at the first invalid block.
+async fn longer_chain(client: &Client, chain: &Chain, rules: &ValidationRules, now: Time) ->
+    Result<Option<Chain>, RequestError> {
+    let num_of_blocks = client.info().await?.height;
+    
+    if num_of_blocks <= chain.num_of_blocks() {
+        return Ok(None);
+    }
+    
+    let common_height = common_height(client, chain, num_of_blocks).await?;
+    let url = format!("{}/chain/stream", client.base_url());
+    let mut blocks = client.chain_stream_from(common_height + 1).await?;
+    
+    // Without a common block the peer's chain starts over with its own genesis block.
+    let mut new_chain = match common_height {
+        0 => None,
+        _ => {
+            let mut new_chain = chain.clone();
+            new_chain.truncate(common_height);
+            Some(new_chain)
         },
+    };
+    
+    while let Some(block) = blocks.next().await {
+        let block = block?;
+        
+        let result = match new_chain.as_mut() {
+            Some(new_chain) => new_chain.push_valid(block, rules, now).map(|_| ()),
+            None => {
+                let genesis_chain = Chain::new(&block);
+                let result = genesis_chain.validate(rules, now);
+                new_chain = Some(genesis_chain);
+                result
+            },
+        };
+        
+        if let Err(error) = result {
+            return Err(RequestError::InvalidChain(url, error));
+        }
+    }
+    
+    match new_chain {
+        Some(new_chain) => Ok(Some(new_chain)),
+        None => Err(RequestError::ParseError(url)),
+    }
+}
+
+// Steps back from the shorter tip in doubling steps until the peer's block is also ours; zero means that even the
+// genesis blocks differ.
+async fn common_height(client: &Client, chain: &Chain, num_of_blocks: usize) -> Result<usize, RequestError> {
+    let mut index = chain.num_of_blocks().min(num_of_blocks);
+    let mut step = 1;
+    
+    loop {
+        let block = client.block(index).await?;
+        
+        if chain.height(&block.hash()) == Some(index) {
+            return Ok(index);
+        }
+        
+        if index == block::INITIAL_INDEX {
+            return Ok(0);
+        }
+        
+        index = index.saturating_sub(step).max(block::INITIAL_INDEX);
+        step *= 2;
     }
 }
+
+fn runtime() -> &'static Runtime {
+    RUNTIME.get_or_init(|| runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("peer-requests")
+        .enable_all()
+        .build()
+        .expect("Peer request runtime must start"))
+}
@@ -0,0 +1,196 @@
+//! Coin emission.
+
+use serde::{Deserialize, Serialize};
+
+use crate::block;
+
+/// Sender of mining reward transactions. Regular transactions may not use it.
+pub const SENDER_FOR_MINING: &str = "Blockchain";
+
+/// Mining reward rules: an initial reward halved every `halving_interval` blocks, capped by `max_supply`.
+///
+/// A zero `halving_interval` keeps the reward constant.
+#[derive(Clone, Copy, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct EmissionSchedule {
+    initial_reward: i64,
+    halving_interval: usize,
+    max_supply: i64,
+}
+
+impl Default for EmissionSchedule {
+    fn default() -> EmissionSchedule {
+        EmissionSchedule {
+            initial_reward: 1,
+            halving_interval: 210_000,
+            max_supply: 210_000,
+        }
+    }
+}
+
+/// Circulating and maximum supply of coins.
+#[derive(Serialize, Deserialize)]
+pub struct Supply {
+    circulating_supply: i64,
+    max_supply: i64,
+}
+
+impl Supply {
+    /// Returns the coins paid out as mining rewards so far.
+    pub fn circulating_supply(&self) -> i64 {
+        self.circulating_supply
+    }
+    
+    /// Returns the most coins that will ever be paid out.
+    pub fn max_supply(&self) -> i64 {
+        self.max_supply
+    }
+}
+
+impl EmissionSchedule {
+    /// Returns the reward for the block with `index`; the genesis block has none.
+    pub fn reward(&self, index: usize) -> i64 {
+        let num_of_mined_blocks = match index.checked_sub(block::INITIAL_INDEX) {
+            Some(num_of_mined_blocks) if num_of_mined_blocks > 0 => num_of_mined_blocks,
+            _ => {
+                return 0;
+            },
+        };
+        
+        let remaining_supply = self.max_supply.saturating_sub(self.emitted(num_of_mined_blocks - 1)).max(0);
+        self.scheduled_reward(num_of_mined_blocks).min(remaining_supply)
+    }
+    
+    /// Returns a message for every invalid parameter.
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        
+        if self.initial_reward < 0 {
+            errors.push(String::from("emission_schedule.initial_reward must not be negative"));
+        }
+        
+        if self.max_supply < 0 {
+            errors.push(String::from("emission_schedule.max_supply must not be negative"));
+        }
+        
+        errors
+    }
+    
+    /// Returns the supply after a chain of `num_of_blocks` blocks.
+    pub fn supply(&self, num_of_blocks: usize) -> Supply {
+        Supply {
+            circulating_supply: self.emitted(num_of_blocks.saturating_sub(block::INITIAL_INDEX)),
+            max_supply: self.max_supply,
+        }
+    }
+    
+    fn scheduled_reward(&self, num_of_mined_blocks: usize) -> i64 {
+        if self.halving_interval == 0 {
+            return self.initial_reward;
+        }
+        
+        let num_of_halvings = (num_of_mined_blocks - 1) / self.halving_interval;
+        
+        match u32::try_from(num_of_halvings) {
+            Ok(num_of_halvings) => self.initial_reward.checked_shr(num_of_halvings).unwrap_or(0),
+            Err(_) => 0,
+        }
+    }
+    
+    fn emitted(&self, num_of_mined_blocks: usize) -> i64 {
+        let mut emitted: i64 = 0;
+        let mut num_of_blocks_left = num_of_mined_blocks;
+        let mut era_reward = self.initial_reward;
+        
+        while num_of_blocks_left > 0 && era_reward > 0 && emitted < self.max_supply {
+            let num_of_blocks_in_era = if self.halving_interval == 0 {
+                num_of_blocks_left
+            } else {
+                num_of_blocks_left.min(self.halving_interval)
+            };
+            
+            let era_emission = era_reward.saturating_mul(i64::try_from(num_of_blocks_in_era).unwrap_or(i64::MAX));
+            emitted = emitted.saturating_add(era_emission);
+            num_of_blocks_left -= num_of_blocks_in_era;
+            era_reward >>= 1;
+        }
+        
+        emitted.min(self.max_supply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn schedule(initial_reward: i64, halving_interval: usize, max_supply: i64) -> EmissionSchedule {
+        EmissionSchedule {
+            initial_reward,
+            halving_interval,
+            max_supply,
+        }
+    }
+    
+    fn rewards(schedule: &EmissionSchedule, indexes: std::ops::RangeInclusive<usize>) -> Vec<i64> {
+        indexes.map(|index| schedule.reward(index)).collect()
+    }
+    
+    #[test]
+    fn genesis_block_has_no_reward() {
+        let schedule = schedule(8, 10, 1000);
+        
+        assert_eq!(rewards(&schedule, 0..=2), [0, 0, 8]);
+        assert_eq!(schedule.supply(1).circulating_supply(), 0);
+        assert_eq!(schedule.supply(0).circulating_supply(), 0);
+    }
+    
+    #[test]
+    fn reward_halves_after_interval() {
+        let schedule = schedule(8, 10, 1000);
+        
+        assert_eq!(rewards(&schedule, 10..=13), [8, 8, 4, 4]);
+        assert_eq!(rewards(&schedule, 21..=22), [4, 2]);
+        assert_eq!(schedule.supply(11).circulating_supply(), 80);
+        assert_eq!(schedule.supply(12).circulating_supply(), 84);
+    }
+    
+    #[test]
+    fn cap_is_reached_within_an_era() {
+        let schedule = schedule(10, 100, 25);
+        
+        assert_eq!(rewards(&schedule, 2..=5), [10, 10, 5, 0]);
+        assert_eq!(schedule.supply(4).circulating_supply(), 25);
+        assert_eq!(schedule.supply(1000).circulating_supply(), 25);
+    }
+    
+    #[test]
+    fn zero_interval_keeps_reward_constant() {
+        let schedule = schedule(3, 0, 10);
+        
+        assert_eq!(rewards(&schedule, 2..=6), [3, 3, 3, 1, 0]);
+        assert_eq!(schedule.supply(6).circulating_supply(), 10);
+    }
+    
+    #[test]
+    fn huge_values_saturate() {
+        let schedule = schedule(i64::MAX, 0, i64::MAX);
+        
+        assert_eq!(rewards(&schedule, 2..=3), [i64::MAX, 0]);
+        assert_eq!(schedule.supply(usize::MAX).circulating_supply(), i64::MAX);
+        assert_eq!(self::schedule(i64::MAX, 1, i64::MAX).reward(usize::MAX), 0);
+    }
+    
+    #[test]
+    fn supply_is_sum_of_rewards() {
+        for schedule in [schedule(8, 10, 1000), schedule(10, 100, 25), schedule(3, 0, 10), schedule(50, 3, 260),
+            EmissionSchedule::default()] {
+            let mut sum = 0;
+            
+            for num_of_blocks in 1..=100 {
+                sum += schedule.reward(num_of_blocks);
+                
+                assert_eq!(schedule.supply(num_of_blocks).circulating_supply(), sum);
+            }
+        }
+    }
+}
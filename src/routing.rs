@@ -1,155 +1,574 @@
-use std::sync::{Arc, Mutex};
+use std::collections::HashSet;
 use std::fmt::{Display, Formatter, Error};
-use actix_web::{web, web::ServiceConfig, HttpResponse, ResponseError};
+use futures::stream;
+use tracing::{warn, Span};
+use serde::{Serialize, de::DeserializeOwned};
+use actix_web::{web, web::{Bytes, ServiceConfig}, http::{header, StatusCode}, HttpRequest, HttpResponse, ResponseError};
 
-use crate::{nodes, transaction, chain, requests, blockchain};
+use simple_blockchain::{encoding, transaction, block, chain, api};
+use crate::{metrics, nodes, requests, blockchain, state};
 
+use encoding::EncodingError;
 use nodes::RegisterError;
-use transaction::Transaction;
-use chain::ChainError;
+use transaction::TransactionError;
+use chain::{Chain, ChainError};
 use requests::RequestError;
-use blockchain::Blockchain;
+use blockchain::{Blockchain, MiningError};
+use state::{State, StateError};
+use api::{ErrorResponse, OneOrMany, FieldError, NodeRequest, ChainStreamQuery, TransactionRequest, MineRequest,
+    ValidationReport, HealthReport, NodeInfo, MiningStatus, ResolvingResult};
+
+const CHAIN_PAYLOAD_LIMIT: usize = 64 * 1024 * 1024;
+const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
 
 #[derive(Debug)]
 pub enum ApiError {
     RegisterError(RegisterError),
     RequestError(RequestError),
     ChainError(ChainError),
+    TransactionError(TransactionError),
+    MiningError(MiningError),
+    InvalidRequest(String),
+    RouteNotFound(String),
+    ValidationErrors(Vec<FieldError>),
+    EncodingError(EncodingError),
+    StateError(StateError),
+}
+
+impl ApiError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::RegisterError(RegisterError::ParseError(_)) => "invalid_node_url",
+            ApiError::RegisterError(RegisterError::EqualsToTheCurrentNode) => "node_is_current",
+            ApiError::RegisterError(RegisterError::HasBeenRegistered) => "node_already_registered",
+            ApiError::RegisterError(RegisterError::NotRegistered) => "node_not_found",
+            ApiError::RequestError(RequestError::RequestError(_)) => "peer_unreachable",
+            ApiError::RequestError(RequestError::ParseError(_)) => "peer_invalid_response",
+            ApiError::RequestError(RequestError::InvalidChain(_, _)) => "peer_invalid_chain",
+            ApiError::ChainError(ChainError::InvalidIndex) => "block_not_found",
+            ApiError::TransactionError(TransactionError::ReservedSender) => "reserved_sender",
+            ApiError::MiningError(MiningError::NoPayoutAddress) => "payout_address_missing",
+            ApiError::InvalidRequest(_) => "invalid_request",
+            ApiError::RouteNotFound(_) => "route_not_found",
+            ApiError::ValidationErrors(_) => "validation_failed",
+            ApiError::EncodingError(_) => "encoding_failed",
+            ApiError::StateError(_) => "state_unavailable",
+        }
+    }
+    
+    fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            ApiError::ValidationErrors(errors) => serde_json::to_value(errors).ok(),
+            ApiError::RequestError(RequestError::InvalidChain(_, error)) => serde_json::to_value(error).ok(),
+            _ => None,
+        }
+    }
 }
 
 impl Display for ApiError {
     fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
-        write!(fmt, "{}", self)
+        match self {
+            ApiError::RegisterError(error) => write!(fmt, "Node registration error: {}", error),
+            ApiError::RequestError(error) => write!(fmt, "HTTP-request error: {}", error),
+            ApiError::ChainError(error) => write!(fmt, "Chain error: {}", error),
+            ApiError::TransactionError(error) => write!(fmt, "Transaction error: {}", error),
+            ApiError::MiningError(error) => write!(fmt, "Mining error: {}", error),
+            ApiError::InvalidRequest(error) => write!(fmt, "Invalid request: {}", error),
+            ApiError::RouteNotFound(route) => write!(fmt, "No route matches {}", route),
+            ApiError::ValidationErrors(errors) => write!(fmt, "Invalid request: {} invalid field(s)", errors.len()),
+            ApiError::EncodingError(error) => write!(fmt, "Encoding error: {}", error),
+            ApiError::StateError(error) => write!(fmt, "Blockchain state is unavailable: {}", error),
+        }
     }
 }
 
 impl ResponseError for ApiError {
-    fn error_response(&self) -> HttpResponse {
+    fn status_code(&self) -> StatusCode {
         match self {
-            ApiError::RegisterError(_) => HttpResponse::BadRequest().json(format!("Node registration error: {}", self)),
-            ApiError::RequestError(_) => HttpResponse::BadRequest().json(format!("HTTP-request error: {}", self)),
-            ApiError::ChainError(_) => HttpResponse::BadRequest().json(format!("Chain error: {}", self)),
+            ApiError::RegisterError(RegisterError::HasBeenRegistered) => StatusCode::CONFLICT,
+            ApiError::RegisterError(RegisterError::NotRegistered) => StatusCode::NOT_FOUND,
+            ApiError::RegisterError(_) => StatusCode::BAD_REQUEST,
+            ApiError::RequestError(_) => StatusCode::BAD_GATEWAY,
+            ApiError::ChainError(ChainError::InvalidIndex) => StatusCode::NOT_FOUND,
+            ApiError::TransactionError(_) => StatusCode::BAD_REQUEST,
+            ApiError::MiningError(MiningError::NoPayoutAddress) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::RouteNotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::ValidationErrors(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::EncodingError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::StateError(_) => StatusCode::SERVICE_UNAVAILABLE,
         }
     }
+    
+    fn error_response(&self) -> HttpResponse {
+        let error_response = ErrorResponse::new(self.code(), self.to_string().as_str(), self.details());
+        HttpResponse::build(self.status_code()).json(error_response)
+    }
 }
 
 pub fn initialize(service_config: &mut ServiceConfig) {
+    service_config.app_data(web::PathConfig::default()
+        .error_handler(|error, _| ApiError::InvalidRequest(error.to_string()).into()));
+    service_config.app_data(web::QueryConfig::default()
+        .error_handler(|error, _| ApiError::InvalidRequest(error.to_string()).into()));
+    
+    service_config.route("/health", web::get().to(health));
+    service_config.route("/info", web::get().to(info));
+    service_config.route("/metrics", web::get().to(metrics));
+    
     service_config.route("/nodes", web::get().to(nodes));
-    service_config.route("/nodes?url={url}", web::post().to(register_node));
+    service_config.route("/nodes", web::post().to(register_nodes));
+    service_config.route("/nodes/{node}", web::delete().to(remove_node));
     
     service_config.route("/chain", web::get().to(chain));
+    service_config.route("/chain/stream", web::get().to(chain_stream));
     service_config.route("/chain/block/{index}", web::get().to(block));
     service_config.route("/chain/resolve_conflicts", web::put().to(resolve_conflicts));
+    service_config.service(web::resource("/chain/validate")
+        .app_data(web::JsonConfig::default()
+            .limit(CHAIN_PAYLOAD_LIMIT)
+            .error_handler(|error, _| ApiError::InvalidRequest(error.to_string()).into()))
+        .route(web::post().to(validate_chain)));
     
     service_config.route("/mine", web::post().to(mine));
+    service_config.route("/supply", web::get().to(supply));
     
     service_config.route("/current_transactions", web::get().to(current_transactions));
-    service_config.route("/current_transactions?sender={sender}&recipient={recipient}&amount={amount}",
-        web::post().to(add_transaction_to_current_transactions));
+    service_config.route("/transactions", web::post().to(add_transactions_to_current_transactions));
 }
 
-pub async fn nodes(blockchain: web::Data<Arc<Mutex<Blockchain>>>) -> Result<HttpResponse, ApiError> {
-    let blockchain = match blockchain.lock() {
-        Ok(blockchain) => blockchain,
-        Err(_) => {
-            return Err(ApiError::RegisterError(RegisterError::BlockchainLocked));
-        }
-    };
+fn parse_body<T: DeserializeOwned>(body: &Bytes) -> Result<T, ApiError> {
+    match serde_json::from_slice::<T>(body) {
+        Ok(value) => Ok(value),
+        Err(error) => Err(ApiError::InvalidRequest(error.to_string())),
+    }
+}
+
+fn batch_response<T: Serialize>(is_batch: bool, items: Vec<T>) -> HttpResponse {
+    if is_batch {
+        HttpResponse::Ok().json(items)
+    } else {
+        HttpResponse::Ok().json(items.first())
+    }
+}
+
+pub async fn not_found(request: HttpRequest) -> Result<HttpResponse, ApiError> {
+    Err(ApiError::RouteNotFound(format!("{} {}", request.method(), request.path())))
+}
+
+pub async fn health(state: web::Data<State>) -> HttpResponse {
+    let health_report = HealthReport::new(state.is_running());
+    
+    if health_report.is_ready() {
+        HttpResponse::Ok().json(health_report)
+    } else {
+        HttpResponse::ServiceUnavailable().json(health_report)
+    }
+}
+
+pub async fn info(state: web::Data<State>) -> Result<HttpResponse, ApiError> {
+    let blockchain = state.snapshot();
+    
+    Ok(HttpResponse::Ok().json(node_info(&blockchain, state.is_mining())))
+}
+
+fn node_info(blockchain: &Blockchain, is_mining: bool) -> NodeInfo {
+    let tip = blockchain.chain().last().expect("Blockchain must containt at least one block");
+    
+    NodeInfo {
+        version: String::from(env!("CARGO_PKG_VERSION")),
+        network_id: String::from(blockchain.network_id()),
+        net_location: String::from(blockchain.net_location()),
+        height: blockchain.chain().num_of_blocks(),
+        tip_hash: tip.hash(),
+        mempool_size: blockchain.current_transactions().len(),
+        peer_count: blockchain.nodes().len(),
+        mining: MiningStatus {
+            enabled: blockchain.payout_address().is_some(),
+            active: is_mining,
+            payout_address: blockchain.payout_address(),
+        },
+        uptime_secs: blockchain.uptime().as_secs(),
+    }
+}
+
+pub async fn metrics(state: web::Data<State>) -> HttpResponse {
+    let blockchain = state.snapshot();
+    
+    HttpResponse::Ok().content_type(metrics::CONTENT_TYPE).body(blockchain.metrics().render(&blockchain))
+}
+
+pub async fn nodes(state: web::Data<State>) -> Result<HttpResponse, ApiError> {
+    let blockchain = state.snapshot();
     
     Ok(HttpResponse::Ok().json(blockchain.nodes()))
 }
 
-pub async fn register_node(blockchain: web::Data<Arc<Mutex<Blockchain>>>, web::Path(url): web::Path<String>) ->
-    Result<HttpResponse, ApiError> {
-    let mut blockchain = match blockchain.lock() {
-        Ok(blockchain) => blockchain,
-        Err(_) => {
-            return Err(ApiError::RegisterError(RegisterError::BlockchainLocked));
+pub async fn register_nodes(state: web::Data<State>, body: Bytes) -> Result<HttpResponse, ApiError> {
+    let node_requests = parse_body::<OneOrMany<NodeRequest>>(&body)?;
+    let is_batch = node_requests.is_batch();
+    let blockchain = state.snapshot();
+    
+    let mut urls = Vec::new();
+    let mut net_locations = HashSet::new();
+    let mut errors = Vec::new();
+    
+    for (item, node_request) in node_requests.into_vec().iter().enumerate() {
+        let result = match node_request.validate() {
+            Ok(url) => match blockchain.check_node(url.as_str()) {
+                Ok(node) => {
+                    if net_locations.insert(String::from(node.net_location())) {
+                        urls.push(url);
+                        Ok(())
+                    } else {
+                        Err(vec![FieldError::new("url", "is repeated in the request")])
+                    }
+                },
+                Err(error @ (RegisterError::HasBeenRegistered | RegisterError::EqualsToTheCurrentNode)) => {
+                    return Err(ApiError::RegisterError(error));
+                },
+                Err(error) => Err(vec![FieldError::new("url", error.to_string().as_str())]),
+            },
+            Err(field_errors) => Err(field_errors),
+        };
+        
+        if let Err(field_errors) = result {
+            errors.extend(field_errors.into_iter().map(|error| if is_batch { error.for_item(item) } else { error }));
         }
-    };
+    }
+    
+    if !errors.is_empty() {
+        return Err(ApiError::ValidationErrors(errors));
+    }
     
-    let node = blockchain.register_node(url.as_str());
+    // Host names are resolved on the blocking pool, neither on this worker nor on the owner thread. A lookup that
+    // can't run leaves the node to the owner's check of the known net locations.
+    let local_addresses = blockchain.local_addresses().clone();
+    let resolves_to_node = web::block(move || Ok::<_, ()>(net_locations.iter()
+        .any(|net_location| local_addresses.resolves_to_node(net_location))));
     
-    match node {
-        Ok(node) => Ok(HttpResponse::Ok().json(node)),
-        Err(error) => Err(ApiError::RegisterError(error)),
+    if matches!(resolves_to_node.await, Ok(true)) {
+        return Err(ApiError::RegisterError(RegisterError::EqualsToTheCurrentNode));
+    }
+    
+    match state.register_nodes(urls).await {
+        Ok(Ok(nodes)) => Ok(batch_response(is_batch, nodes)),
+        Ok(Err(error)) => Err(ApiError::RegisterError(error)),
+        Err(error) => Err(ApiError::StateError(error)),
     }
 }
 
-pub async fn chain(blockchain: web::Data<Arc<Mutex<Blockchain>>>) -> Result<HttpResponse, ApiError> {
-    let blockchain = match blockchain.lock() {
-        Ok(blockchain) => blockchain,
-        Err(_) => {
-            return Err(ApiError::RegisterError(RegisterError::BlockchainLocked));
-        }
-    };
+// Server side of `blockchain-cli peers remove`; `node` is a peer's net location.
+pub async fn remove_node(state: web::Data<State>, web::Path(node): web::Path<String>) ->
+    Result<HttpResponse, ApiError> {
+    match state.remove_node(node).await {
+        Ok(Ok(node)) => Ok(HttpResponse::Ok().json(node)),
+        Ok(Err(error)) => Err(ApiError::RegisterError(error)),
+        Err(error) => Err(ApiError::StateError(error)),
+    }
+}
+
+fn encoded_response<T: Serialize>(request: &HttpRequest, value: &T) -> Result<HttpResponse, ApiError> {
+    let accept = request.headers().get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .unwrap_or_default();
+    
+    if !encoding::accepts_binary(accept) {
+        return Ok(HttpResponse::Ok().json(value));
+    }
     
-    Ok(HttpResponse::Ok().json(blockchain.chain()))
+    match encoding::to_binary(value) {
+        Ok(bytes) => Ok(HttpResponse::Ok().content_type(encoding::BINARY_CONTENT_TYPE).body(bytes)),
+        Err(error) => Err(ApiError::EncodingError(error)),
+    }
+}
+
+pub async fn chain(request: HttpRequest, state: web::Data<State>) ->
+    Result<HttpResponse, ApiError> {
+    let blockchain = state.snapshot();
+    
+    encoded_response(&request, blockchain.chain())
 }
 
-pub async fn block(blockchain: web::Data<Arc<Mutex<Blockchain>>>, web::Path(index): web::Path<usize>) ->
+pub async fn chain_stream(request: HttpRequest, state: web::Data<State>, query: web::Query<ChainStreamQuery>) ->
     Result<HttpResponse, ApiError> {
-     let blockchain = match blockchain.lock() {
-        Ok(blockchain) => blockchain,
-        Err(_) => {
-            return Err(ApiError::RegisterError(RegisterError::BlockchainLocked));
+    let blockchain = state.snapshot();
+    let num_of_blocks = blockchain.chain().num_of_blocks();
+    let from = query.from.unwrap_or(block::INITIAL_INDEX).max(block::INITIAL_INDEX);
+    let is_binary = request.headers().get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(encoding::accepts_binary);
+    
+    let chunks = stream::unfold(from, move |index| {
+        let blockchain = blockchain.clone();
+        
+        async move {
+            if index > num_of_blocks {
+                return None;
+            }
+            
+            match block_chunk(&blockchain, index, is_binary) {
+                Ok(chunk) => Some((Ok(chunk), index + 1)),
+                Err(error) => Some((Err(error), usize::MAX)),
+            }
         }
+    });
+    
+    let content_type = if is_binary { encoding::BINARY_CONTENT_TYPE } else { NDJSON_CONTENT_TYPE };
+    
+    Ok(HttpResponse::Ok().content_type(content_type).streaming(Box::pin(chunks)))
+}
+
+// A block of the chain stream: a bincode frame or a JSON line.
+fn block_chunk(blockchain: &Blockchain, index: usize, is_binary: bool) -> Result<Bytes, ApiError> {
+    let block = match blockchain.block(index) {
+        Ok(block) => block,
+        Err(error) => {
+            return Err(ApiError::ChainError(error));
+        },
     };
     
+    if !is_binary {
+        let mut line = serde_json::to_vec(block).expect("Block must be serializable");
+        line.push(b'\n');
+        return Ok(Bytes::from(line));
+    }
+    
+    match encoding::to_binary_frame(block) {
+        Ok(frame) => Ok(Bytes::from(frame)),
+        Err(error) => Err(ApiError::EncodingError(error)),
+    }
+}
+
+pub async fn block(request: HttpRequest, state: web::Data<State>,
+    web::Path(index): web::Path<usize>) -> Result<HttpResponse, ApiError> {
+    Span::current().record("block_index", index);
+    let blockchain = state.snapshot();
+    
     let block = blockchain.block(index);
     
     match block {
-        Ok(block) => Ok(HttpResponse::Ok().json(block)),
+        Ok(block) => encoded_response(&request, block),
         Err(error) => Err(ApiError::ChainError(error)),
     }
 }
 
-pub async fn resolve_conflicts(blockchain: web::Data<Arc<Mutex<Blockchain>>>) -> Result<HttpResponse, ApiError> {
-    let mut blockchain = match blockchain.lock() {
-        Ok(blockchain) => blockchain,
-        Err(_) => {
-            return Err(ApiError::RegisterError(RegisterError::BlockchainLocked));
-        }
+pub async fn resolve_conflicts(state: web::Data<State>) -> Result<HttpResponse, ApiError> {
+    let blockchain = state.snapshot();
+    
+    let chain = match blockchain.find_longest_chain().await {
+        Ok(chain) => chain,
+        Err(error) => {
+            return Err(ApiError::RequestError(error));
+        },
     };
     
-    let result = blockchain.resolve_conflicts().await;
+    let resolving_result = match chain {
+        Some(chain) => match state.adopt_chain(chain).await {
+            Ok(resolving_result) => resolving_result,
+            Err(error) => {
+                return Err(ApiError::StateError(error));
+            },
+        },
+        None => ResolvingResult::NoColflicts,
+    };
+    
+    Ok(HttpResponse::Ok().json(resolving_result))
+}
+
+pub async fn validate_chain(state: web::Data<State>, web::Json(chain): web::Json<Chain>) ->
+    Result<HttpResponse, ApiError> {
+    let blockchain = state.snapshot();
+    let result = chain.validate(blockchain.rules(), blockchain.clock().now());
     
-    match result {
-        Ok(resolving_result) => Ok(HttpResponse::Ok().json(resolving_result)),
-        Err(error) => Err(ApiError::RequestError(error)),
+    if let Err(error) = &result {
+        warn!(%error, block_index = error.index(), "Chain validation failed");
     }
+    
+    Ok(HttpResponse::Ok().json(ValidationReport::new(result)))
 }
 
-pub async fn mine(blockchain: web::Data<Arc<Mutex<Blockchain>>>) -> Result<HttpResponse, ApiError> {
-    let mut blockchain = match blockchain.lock() {
-        Ok(blockchain) => blockchain,
-        Err(_) => {
-            return Err(ApiError::RegisterError(RegisterError::BlockchainLocked));
+pub async fn mine(state: web::Data<State>, body: Bytes) -> Result<HttpResponse, ApiError> {
+    let payout_address = if body.iter().all(|byte| byte.is_ascii_whitespace()) {
+        None
+    } else {
+        match serde_json::from_slice::<MineRequest>(&body) {
+            Ok(mine_request) => mine_request.payout_address(),
+            Err(error) => {
+                return Err(ApiError::InvalidRequest(error.to_string()));
+            },
         }
     };
     
-    Ok(HttpResponse::Ok().json(blockchain.mine()))
+    match state.mine(payout_address).await {
+        Ok(Ok(block)) => Ok(HttpResponse::Ok().json(block)),
+        Ok(Err(error)) => Err(ApiError::MiningError(error)),
+        Err(error) => Err(ApiError::StateError(error)),
+    }
 }
 
-pub async fn current_transactions(blockchain: web::Data<Arc<Mutex<Blockchain>>>) -> Result<HttpResponse, ApiError> {
-    let blockchain = match blockchain.lock() {
-        Ok(blockchain) => blockchain,
-        Err(_) => {
-            return Err(ApiError::RegisterError(RegisterError::BlockchainLocked));
-        }
-    };
+pub async fn supply(state: web::Data<State>) -> Result<HttpResponse, ApiError> {
+    let blockchain = state.snapshot();
+    
+    Ok(HttpResponse::Ok().json(blockchain.supply()))
+}
+
+pub async fn current_transactions(state: web::Data<State>) -> Result<HttpResponse, ApiError> {
+    let blockchain = state.snapshot();
     
     Ok(HttpResponse::Ok().json(blockchain.current_transactions()))
 }
 
-pub async fn add_transaction_to_current_transactions(blockchain: web::Data<Arc<Mutex<Blockchain>>>,
-    web::Path(transaction): web::Path<Transaction>) -> Result<HttpResponse, ApiError> {
-    let mut blockchain = match blockchain.lock() {
-        Ok(blockchain) => blockchain,
-        Err(_) => {
-            return Err(ApiError::RegisterError(RegisterError::BlockchainLocked));
+pub async fn add_transactions_to_current_transactions(state: web::Data<State>, body: Bytes) ->
+    Result<HttpResponse, ApiError> {
+    let transaction_requests = parse_body::<OneOrMany<TransactionRequest>>(&body)?;
+    let is_batch = transaction_requests.is_batch();
+    let mut transactions = Vec::new();
+    let mut errors = Vec::new();
+    
+    for (item, transaction_request) in transaction_requests.into_vec().iter().enumerate() {
+        match transaction_request.validate() {
+            Ok(transaction) => transactions.push(transaction),
+            Err(field_errors) => errors.extend(field_errors.into_iter()
+                .map(|error| if is_batch { error.for_item(item) } else { error })),
         }
-    };
+    }
+    
+    if !errors.is_empty() {
+        return Err(ApiError::ValidationErrors(errors));
+    }
     
-    Ok(HttpResponse::Ok().json(blockchain.add_transaction_to_current_transactions(transaction)))
+    match state.add_transactions(transactions).await {
+        Ok(Ok(transactions)) => Ok(batch_response(is_batch, transactions)),
+        Ok(Err(error)) => Err(ApiError::TransactionError(error)),
+        Err(error) => Err(ApiError::StateError(error)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use actix_web::{test, App};
+    
+    use simple_blockchain::{net_location, rules, clock};
+    use crate::metrics::Metrics;
+    
+    use net_location::LocalAddresses;
+    use rules::ValidationRules;
+    use clock::SystemClock;
+    
+    use super::*;
+    
+    fn state() -> State {
+        state_with(LocalAddresses::new("127.0.0.1:8080", None).unwrap())
+    }
+    
+    fn state_with(local_addresses: LocalAddresses) -> State {
+        State::start(Blockchain::new("test", local_addresses, None, ValidationRules::default(), Arc::new(SystemClock),
+            Arc::new(Metrics::new())))
+    }
+    
+    #[actix_rt::test]
+    async fn remove_node_by_net_location() {
+        let state = state();
+        state.register_nodes(vec![String::from("http://[::FFFF:127.0.0.2]:9090")]).await.unwrap().unwrap();
+        let mut service = test::init_service(App::new().data(state.clone()).configure(initialize)).await;
+        
+        let request = test::TestRequest::delete().uri("/nodes/127.0.0.2:9090").to_request();
+        let node: serde_json::Value = test::read_response_json(&mut service, request).await;
+        
+        assert_eq!(node["net_location"], "127.0.0.2:9090");
+        assert_eq!(state.snapshot().nodes().len(), 0);
+        
+        let request = test::TestRequest::delete().uri("/nodes/127.0.0.2:9090").to_request();
+        let response = test::call_service(&mut service, request).await;
+        
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(test::read_body_json::<ErrorResponse, _>(response).await.error.code, "node_not_found");
+    }
+    
+    #[actix_rt::test]
+    async fn register_node_twice_conflicts() {
+        let mut service = test::init_service(App::new().data(state()).configure(initialize)).await;
+        let node_request = serde_json::json!({"url": "http://127.0.0.2:9090"});
+        
+        let request = test::TestRequest::post().uri("/nodes").set_json(&node_request).to_request();
+        let response = test::call_service(&mut service, request).await;
+        
+        assert_eq!(response.status(), StatusCode::OK);
+        
+        let request = test::TestRequest::post().uri("/nodes").set_json(&node_request).to_request();
+        let response = test::call_service(&mut service, request).await;
+        
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert_eq!(test::read_body_json::<ErrorResponse, _>(response).await.error.code, "node_already_registered");
+        
+        let request = test::TestRequest::post().uri("/nodes").set_json(&vec![node_request]).to_request();
+        let response = test::call_service(&mut service, request).await;
+        
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+    
+    #[actix_rt::test]
+    async fn register_current_node_is_rejected() {
+        let mut service = test::init_service(App::new().data(state()).configure(initialize)).await;
+        
+        let request = test::TestRequest::post().uri("/nodes")
+            .set_json(&serde_json::json!({"url": "http://127.0.0.1:8080"}))
+            .to_request();
+        let response = test::call_service(&mut service, request).await;
+        
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(test::read_body_json::<ErrorResponse, _>(response).await.error.code, "node_is_current");
+    }
+    
+    #[actix_rt::test]
+    async fn register_node_resolving_to_current_node_is_rejected() {
+        let local_addresses = LocalAddresses::new("0.0.0.0:8080", Some("http://192.0.2.1:8080")).unwrap();
+        let mut service = test::init_service(App::new().data(state_with(local_addresses)).configure(initialize)).await;
+        
+        let request = test::TestRequest::post().uri("/nodes")
+            .set_json(&serde_json::json!([{"url": "http://127.0.0.2:8081"}, {"url": "http://127.0.0.2:8080"}]))
+            .to_request();
+        let response = test::call_service(&mut service, request).await;
+        
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(test::read_body_json::<ErrorResponse, _>(response).await.error.code, "node_is_current");
+    }
+    
+    #[actix_rt::test]
+    async fn chain_stream_is_framed_when_binary_is_accepted() {
+        let state = state();
+        let genesis_hash = state.snapshot().chain().block(block::INITIAL_INDEX).unwrap().hash();
+        let mut service = test::init_service(App::new().data(state).configure(initialize)).await;
+        
+        let request = test::TestRequest::get().uri("/chain/stream")
+            .header(header::ACCEPT, encoding::BINARY_CONTENT_TYPE)
+            .to_request();
+        let response = test::call_service(&mut service, request).await;
+        
+        assert_eq!(response.headers().get(header::CONTENT_TYPE).unwrap(), encoding::BINARY_CONTENT_TYPE);
+        
+        let body = test::read_body(response).await;
+        let block = encoding::from_binary::<block::Block>(&body[encoding::FRAME_HEADER_LENGTH..]).unwrap();
+        
+        assert_eq!(encoding::frame_length(&body), Some(body.len() - encoding::FRAME_HEADER_LENGTH));
+        assert_eq!(block.hash(), genesis_hash);
+        
+        let request = test::TestRequest::get().uri("/chain/stream").to_request();
+        let response = test::call_service(&mut service, request).await;
+        
+        assert_eq!(response.headers().get(header::CONTENT_TYPE).unwrap(), NDJSON_CONTENT_TYPE);
+    }
+    
+    #[actix_rt::test]
+    async fn remove_node_with_invalid_net_location() {
+        let mut service = test::init_service(App::new().data(state()).configure(initialize)).await;
+        
+        let request = test::TestRequest::delete().uri("/nodes/127.0.0.2:port").to_request();
+        let response = test::call_service(&mut service, request).await;
+        
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(test::read_body_json::<ErrorResponse, _>(response).await.error.code, "invalid_node_url");
+    }
 }
@@ -0,0 +1,78 @@
+//! Consensus parameters.
+
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+
+use crate::reward;
+
+use reward::EmissionSchedule;
+
+/// Rules for block timestamps.
+///
+/// A block must be dated after the median time of the previous `median_time_span` blocks and at most
+/// `max_future_drift_secs` ahead of the validating node's clock.
+#[derive(Clone, Copy, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TimestampRules {
+    median_time_span: usize,
+    max_future_drift_secs: u64,
+}
+
+impl Default for TimestampRules {
+    fn default() -> TimestampRules {
+        TimestampRules {
+            median_time_span: 11,
+            max_future_drift_secs: 2 * 60 * 60,
+        }
+    }
+}
+
+impl TimestampRules {
+    /// Returns the number of previous blocks the median time is taken over, at least one.
+    pub fn median_time_span(&self) -> usize {
+        self.median_time_span.max(1)
+    }
+    
+    /// Returns how far ahead of the local clock a block may be dated.
+    pub fn max_future_drift(&self) -> Duration {
+        Duration::from_secs(self.max_future_drift_secs)
+    }
+    
+    /// Returns a message for every invalid parameter.
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        
+        if self.median_time_span == 0 {
+            errors.push(String::from("timestamp_rules.median_time_span must be positive"));
+        }
+        
+        errors
+    }
+}
+
+/// Consensus parameters a chain is validated against.
+#[derive(Clone, Copy, Default)]
+pub struct ValidationRules {
+    emission_schedule: EmissionSchedule,
+    timestamp_rules: TimestampRules,
+}
+
+impl ValidationRules {
+    /// Combines the emission schedule and the timestamp rules.
+    pub fn new(emission_schedule: EmissionSchedule, timestamp_rules: TimestampRules) -> ValidationRules {
+        ValidationRules {
+            emission_schedule,
+            timestamp_rules,
+        }
+    }
+    
+    /// Returns the mining reward rules.
+    pub fn emission_schedule(&self) -> &EmissionSchedule {
+        &self.emission_schedule
+    }
+    
+    /// Returns the timestamp rules.
+    pub fn timestamp_rules(&self) -> &TimestampRules {
+        &self.timestamp_rules
+    }
+}
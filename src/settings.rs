@@ -1,47 +1,310 @@
-use std::io::prelude::Read;
-use std::fs::File;
-use serde::Deserialize;
-use serde_json;
-use serde_json::Result;
+use std::fmt::{Display, Formatter, Error};
+use std::fs;
+use std::io;
+use std::net::ToSocketAddrs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize)]
+use simple_blockchain::{address, net_location, reward, rules};
+use crate::{arguments, logging};
+
+use arguments::Arguments;
+use logging::LoggingSettings;
+use address::Address;
+use reward::EmissionSchedule;
+use rules::{TimestampRules, ValidationRules};
+use net_location::LocalAddresses;
+
+#[derive(Debug)]
+pub enum SettingsError {
+    ReadError(PathBuf, io::Error),
+    ParseError(PathBuf, serde_json::Error),
+    InvalidValue(String),
+}
+
+impl Display for SettingsError {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        match self {
+            SettingsError::ReadError(path, error) => write!(fmt, "Can\'t read \"{}\": {}", path.display(), error),
+            SettingsError::ParseError(path, error) => write!(fmt, "Can\'t parse \"{}\": {}", path.display(), error),
+            SettingsError::InvalidValue(message) => write!(fmt, "{}", message),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct Settings {
-    socket_address: String,
+    #[serde(default = "default_network_id")]
+    network_id: String,
+    #[serde(default = "default_bind_address", alias = "socket_address")]
+    bind_address: String,
+    #[serde(default)]
+    public_address: Option<String>,
+    #[serde(default = "default_data_dir")]
+    data_dir: PathBuf,
+    #[serde(default)]
+    peers: Vec<String>,
+    #[serde(default)]
+    mine: bool,
+    #[serde(default)]
+    payout_address: Option<Address>,
+    #[serde(default)]
+    emission_schedule: EmissionSchedule,
+    #[serde(default)]
+    timestamp_rules: TimestampRules,
+    #[serde(default)]
+    logging: LoggingSettings,
 }
 
 const SETTINGS_FILENAME: &str = "settings.json";
+const DEFAULT_NETWORK_ID: &str = "main";
+const DEFAULT_BIND_ADDRESS: &str = "127.0.0.1:8080";
+const DEFAULT_DATA_DIR: &str = "data";
+
+fn default_network_id() -> String {
+    String::from(DEFAULT_NETWORK_ID)
+}
+
+fn default_bind_address() -> String {
+    String::from(DEFAULT_BIND_ADDRESS)
+}
+
+fn default_data_dir() -> PathBuf {
+    PathBuf::from(DEFAULT_DATA_DIR)
+}
 
 impl Settings {
-    pub fn new() -> Settings {
-        Settings::load_from_file()
+    pub fn load(arguments: &Arguments) -> Result<Settings, Vec<SettingsError>> {
+        let mut settings = match arguments.config() {
+            Some(path) => Settings::load_from_file(path),
+            None if Path::new(SETTINGS_FILENAME).exists() => Settings::load_from_file(Path::new(SETTINGS_FILENAME)),
+            None => Settings::load_from_str("{}", Path::new(SETTINGS_FILENAME)),
+        }.map_err(|error| vec![error])?;
+        
+        let mut errors = settings.apply(arguments);
+        errors.extend(settings.validate());
+        
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        
+        Ok(settings)
+    }
+    
+    pub fn network_id(&self) -> &str {
+        self.network_id.as_str()
+    }
+    
+    pub fn bind_address(&self) -> &str {
+        self.bind_address.as_str()
+    }
+    
+    pub fn local_addresses(&self) -> LocalAddresses {
+        LocalAddresses::new(self.bind_address(), self.public_address.as_deref())
+            .expect("Settings must be validated")
     }
     
-    pub fn socket_address(&self) -> &str {
-        self.socket_address.as_str()
+    pub fn data_dir(&self) -> &Path {
+        self.data_dir.as_path()
     }
     
-    pub fn load_from_file() -> Settings {
-        let file = File::open(SETTINGS_FILENAME);
+    pub fn peers(&self) -> &[String] {
+        self.peers.as_slice()
+    }
+    
+    pub fn mine(&self) -> bool {
+        self.mine
+    }
+    
+    pub fn payout_address(&self) -> Option<Address> {
+        self.payout_address
+    }
+    
+    pub fn validation_rules(&self) -> ValidationRules {
+        ValidationRules::new(self.emission_schedule, self.timestamp_rules)
+    }
+    
+    pub fn logging(&self) -> &LoggingSettings {
+        &self.logging
+    }
+    
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Settings must be serializable")
+    }
+    
+    fn load_from_file(path: &Path) -> Result<Settings, SettingsError> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Settings::load_from_str(contents.as_str(), path),
+            Err(error) => Err(SettingsError::ReadError(path.to_path_buf(), error)),
+        }
+    }
+    
+    fn load_from_str(contents: &str, path: &Path) -> Result<Settings, SettingsError> {
+        match serde_json::from_str(contents) {
+            Ok(settings) => Ok(settings),
+            Err(error) => Err(SettingsError::ParseError(path.to_path_buf(), error)),
+        }
+    }
+    
+    fn apply(&mut self, arguments: &Arguments) -> Vec<SettingsError> {
+        let mut errors = Vec::new();
         
-        match file {
-            Ok(_) => {
-            },
-            Err(error) => panic!("Error opening file \"settings.json\": {}", error),
+        if let Some(network_id) = arguments.network_id() {
+            self.network_id = String::from(network_id);
         }
         
-        let mut contents = String::new();
+        if let Some(bind) = arguments.bind() {
+            self.bind_address = String::from(bind);
+        }
         
-        match file.unwrap().read_to_string(&mut contents) {
-            Ok(_) => {
+        if let Some(public_address) = arguments.public_address() {
+            self.public_address = Some(String::from(public_address));
+        }
+        
+        if let Some(data_dir) = arguments.data_dir() {
+            self.data_dir = data_dir.clone();
+        }
+        
+        if !arguments.peers().is_empty() {
+            self.peers = arguments.peers().to_vec();
+        }
+        
+        if arguments.mine() {
+            self.mine = true;
+        }
+        
+        if let Some(payout_address) = arguments.payout_address() {
+            match payout_address.parse::<Address>() {
+                Ok(payout_address) => self.payout_address = Some(payout_address),
+                Err(error) => errors.push(SettingsError::InvalidValue(format!("payout_address is invalid: {}",
+                    error))),
+            }
+        }
+        
+        if let Some(log_filter) = arguments.log_filter() {
+            self.logging.set_filter(log_filter);
+        }
+        
+        if let Some(log_format) = arguments.log_format() {
+            self.logging.set_format(log_format);
+        }
+        
+        errors
+    }
+    
+    fn validate(&self) -> Vec<SettingsError> {
+        let mut errors = Vec::new();
+        
+        if self.network_id.trim().is_empty() {
+            errors.push(String::from("network_id must not be empty"));
+        }
+        
+        match self.bind_address.to_socket_addrs() {
+            Ok(mut socket_addresses) => {
+                if self.public_address.is_none() && socket_addresses.all(|address| address.ip().is_unspecified()) {
+                    errors.push(format!("public_address must be set when bind_address \"{}\" listens on all interfaces",
+                        self.bind_address));
+                }
             },
-            Err(error) => panic!("Error reading file \"settings.json\": {}", error),
+            Err(error) => errors.push(format!("bind_address \"{}\" is invalid: {}", self.bind_address, error)),
+        }
+        
+        if let Some(public_address) = &self.public_address {
+            if let Err(error) = net_location::net_location_by_url(public_address) {
+                errors.push(format!("public_address \"{}\" is invalid: {}", public_address, error));
+            }
+        }
+        
+        if self.data_dir.exists() && !self.data_dir.is_dir() {
+            errors.push(format!("data_dir \"{}\" is not a directory", self.data_dir.display()));
+        }
+        
+        let local_addresses = LocalAddresses::new(self.bind_address(), self.public_address.as_deref()).ok();
+        
+        for peer in self.peers.iter() {
+            match net_location::net_location_by_url(peer) {
+                Ok(net_location) => {
+                    if local_addresses.as_ref().is_some_and(|local_addresses| local_addresses.resolves_to_node(
+                        net_location.as_str())) {
+                        errors.push(format!("peers: \"{}\" is this node", peer));
+                    }
+                },
+                Err(error) => errors.push(format!("peers: \"{}\" is not a valid url: {}", peer, error)),
+            }
         }
         
-        let settings: Result<Settings> = serde_json::from_str(contents.as_str());
+        if self.mine && self.payout_address.is_none() {
+            errors.push(String::from("mine requires payout_address to be set"));
+        }
+        
+        errors.extend(self.emission_schedule.validate());
+        errors.extend(self.timestamp_rules.validate());
+        errors.extend(self.logging.validate());
+        
+        errors.into_iter().map(SettingsError::InvalidValue).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn parse(contents: &str) -> Result<Settings, SettingsError> {
+        Settings::load_from_str(contents, Path::new(SETTINGS_FILENAME))
+    }
+    
+    #[test]
+    fn legacy_socket_address_is_accepted() {
+        let settings = parse(r#"{"socket_address": "127.0.0.1:9090"}"#).unwrap();
+        
+        assert_eq!(settings.bind_address(), "127.0.0.1:9090");
+    }
+    
+    #[test]
+    fn data_dir_must_be_directory() {
+        assert_eq!(parse("{}").unwrap().data_dir(), Path::new(DEFAULT_DATA_DIR));
         
-        match settings {
-            Ok(settings) => settings,
-            Err(error) => panic!("Error reading file \"settings.json\": {}", error),
+        let mut settings = parse(r#"{"data_dir": "Cargo.toml"}"#).unwrap();
+        
+        assert_eq!(settings.validate().len(), 1);
+        
+        settings.data_dir = PathBuf::from("src");
+        
+        assert!(settings.validate().is_empty());
+    }
+    
+    #[test]
+    fn unknown_fields_are_rejected() {
+        assert!(parse(r#"{"bind_adress": "127.0.0.1:9090"}"#).is_err());
+        assert!(parse(r#"{"data_dr": "data"}"#).is_err());
+        assert!(parse(r#"{"emission_schedule": {"initial_reward": 1, "halving_interval": 0, "max_suply": 1}}"#)
+            .is_err());
+        assert!(parse(r#"{"timestamp_rules": {"median_time_span": 11, "max_future_drift_secs": 0, "drift": 1}}"#)
+            .is_err());
+        assert!(parse(r#"{"logging": {"filtre": "debug"}}"#).is_err());
+    }
+    
+    #[test]
+    fn unspecified_bind_address_requires_public_address() {
+        for bind_address in ["0.0.0.0:8080", "[::]:8080"] {
+            let mut settings = parse(format!(r#"{{"bind_address": "{}"}}"#, bind_address).as_str()).unwrap();
+            
+            assert_eq!(settings.validate().len(), 1);
+            
+            settings.public_address = Some(String::from("http://node.example.com:8080"));
+            
+            assert!(settings.validate().is_empty());
         }
+        
+        assert!(parse(r#"{"bind_address": "127.0.0.1:8080"}"#).unwrap().validate().is_empty());
+    }
+    
+    #[test]
+    fn peer_resolving_to_node_is_rejected() {
+        let settings = parse(r#"{"bind_address": "0.0.0.0:8080", "public_address": "http://192.0.2.1:8080",
+            "peers": ["http://127.0.0.2:8080", "http://127.0.0.2:8081"]}"#).unwrap();
+        
+        assert_eq!(settings.validate().len(), 1);
     }
 }
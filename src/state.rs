@@ -0,0 +1,155 @@
+use std::fmt::{Display, Formatter, Error};
+use std::sync::{Arc, mpsc, atomic::{AtomicBool, Ordering}};
+use std::thread::{self, JoinHandle};
+use arc_swap::ArcSwap;
+use futures::channel::oneshot;
+use tracing::Span;
+
+use simple_blockchain::{address, node, transaction, block, chain, api};
+use crate::{nodes, blockchain};
+
+use address::Address;
+use node::Node;
+use nodes::RegisterError;
+use transaction::{Transaction, TransactionError};
+use block::Block;
+use chain::Chain;
+use api::ResolvingResult;
+use blockchain::{Blockchain, MiningError};
+
+#[derive(Debug)]
+pub enum StateError {
+    OwnerStopped,
+}
+
+impl Display for StateError {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        match self {
+            StateError::OwnerStopped => write!(fmt, "Blockchain owner has stopped"),
+        }
+    }
+}
+
+enum Command {
+    RegisterNodes(Vec<String>, oneshot::Sender<Result<Vec<Node>, RegisterError>>),
+    RemoveNode(String, oneshot::Sender<Result<Node, RegisterError>>),
+    AddTransactions(Vec<Transaction>, oneshot::Sender<Result<Vec<Transaction>, TransactionError>>),
+    Mine(Option<Address>, oneshot::Sender<Result<Block, MiningError>>),
+    AdoptChain(Chain, oneshot::Sender<ResolvingResult>),
+}
+
+// Readers load the latest published snapshot without locking. Every mutation is sent to the owner thread, which
+// applies commands one at a time and publishes a new snapshot before replying. Publishing is cheap: the chain is a
+// persistent structure, and the peers and the mempool are shared with the snapshot until they change.
+#[derive(Clone)]
+pub struct State {
+    snapshot: Arc<ArcSwap<Blockchain>>,
+    commands: mpsc::Sender<(Span, Command)>,
+    owner: Arc<JoinHandle<()>>,
+    mining: Arc<AtomicBool>,
+}
+
+impl State {
+    pub fn start(blockchain: Blockchain) -> State {
+        let snapshot = Arc::new(ArcSwap::from_pointee(blockchain.clone()));
+        let (commands, receiver) = mpsc::channel();
+        let mining = Arc::new(AtomicBool::new(false));
+        let owner_snapshot = snapshot.clone();
+        let owner_mining = mining.clone();
+        
+        let owner = thread::Builder::new()
+            .name(String::from("blockchain-owner"))
+            .spawn(move || State::run_owner(blockchain, owner_snapshot, owner_mining, receiver))
+            .expect("Blockchain owner thread must be spawned");
+        
+        State {
+            snapshot,
+            commands,
+            owner: Arc::new(owner),
+            mining,
+        }
+    }
+    
+    pub fn snapshot(&self) -> Arc<Blockchain> {
+        self.snapshot.load_full()
+    }
+    
+    pub fn is_running(&self) -> bool {
+        !self.owner.is_finished()
+    }
+    
+    pub fn is_mining(&self) -> bool {
+        self.mining.load(Ordering::Relaxed)
+    }
+    
+    pub async fn register_nodes(&self, urls: Vec<String>) -> Result<Result<Vec<Node>, RegisterError>, StateError> {
+        self.execute(|sender| Command::RegisterNodes(urls, sender)).await
+    }
+    
+    pub async fn remove_node(&self, url: String) -> Result<Result<Node, RegisterError>, StateError> {
+        self.execute(|sender| Command::RemoveNode(url, sender)).await
+    }
+    
+    pub async fn add_transactions(&self, transactions: Vec<Transaction>) ->
+        Result<Result<Vec<Transaction>, TransactionError>, StateError> {
+        self.execute(|sender| Command::AddTransactions(transactions, sender)).await
+    }
+    
+    pub async fn mine(&self, payout_address: Option<Address>) -> Result<Result<Block, MiningError>, StateError> {
+        self.execute(|sender| Command::Mine(payout_address, sender)).await
+    }
+    
+    pub async fn adopt_chain(&self, chain: Chain) -> Result<ResolvingResult, StateError> {
+        self.execute(|sender| Command::AdoptChain(chain, sender)).await
+    }
+    
+    async fn execute<T>(&self, command: impl FnOnce(oneshot::Sender<T>) -> Command) -> Result<T, StateError> {
+        let (sender, receiver) = oneshot::channel();
+        
+        if self.commands.send((Span::current(), command(sender))).is_err() {
+            return Err(StateError::OwnerStopped);
+        }
+        
+        match receiver.await {
+            Ok(result) => Ok(result),
+            Err(_) => Err(StateError::OwnerStopped),
+        }
+    }
+    
+    fn run_owner(mut blockchain: Blockchain, snapshot: Arc<ArcSwap<Blockchain>>, mining: Arc<AtomicBool>,
+        receiver: mpsc::Receiver<(Span, Command)>) {
+        for (span, command) in receiver {
+            let _entered = span.enter();
+            
+            match command {
+                Command::RegisterNodes(urls, sender) => {
+                    let result = blockchain.register_nodes(&urls);
+                    snapshot.store(Arc::new(blockchain.clone()));
+                    let _ = sender.send(result);
+                },
+                Command::RemoveNode(url, sender) => {
+                    let result = blockchain.remove_node(url.as_str());
+                    snapshot.store(Arc::new(blockchain.clone()));
+                    let _ = sender.send(result);
+                },
+                Command::AddTransactions(transactions, sender) => {
+                    let result = blockchain.add_transactions_to_current_transactions(transactions);
+                    snapshot.store(Arc::new(blockchain.clone()));
+                    let _ = sender.send(result);
+                },
+                Command::Mine(payout_address, sender) => {
+                    mining.store(true, Ordering::Relaxed);
+                    let result = blockchain.mine(payout_address);
+                    mining.store(false, Ordering::Relaxed);
+                    snapshot.store(Arc::new(blockchain.clone()));
+                    let _ = sender.send(result);
+                },
+                Command::AdoptChain(chain, sender) => {
+                    let result = blockchain.adopt_chain(chain);
+                    snapshot.store(Arc::new(blockchain.clone()));
+                    let _ = sender.send(result);
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use std::fmt::{Display, Formatter, Error};
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use simple_blockchain::{chain, rules, time};
+
+use chain::{Chain, ValidationError};
+use rules::ValidationRules;
+use time::Time;
+
+const CHAIN_FILENAME: &str = "chain.json";
+
+#[derive(Debug)]
+pub enum StorageError {
+    ReadError(PathBuf, io::Error),
+    WriteError(PathBuf, io::Error),
+    ParseError(PathBuf, serde_json::Error),
+    InvalidChain(PathBuf, ValidationError),
+}
+
+impl Display for StorageError {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        match self {
+            StorageError::ReadError(path, error) => write!(fmt, "Can\'t read \"{}\": {}", path.display(), error),
+            StorageError::WriteError(path, error) => write!(fmt, "Can\'t write \"{}\": {}", path.display(), error),
+            StorageError::ParseError(path, error) => write!(fmt, "Can\'t parse \"{}\": {}", path.display(), error),
+            StorageError::InvalidChain(path, error) => write!(fmt, "Chain in \"{}\" is invalid: {}", path.display(),
+                error),
+        }
+    }
+}
+
+/// Loads the chain saved in `data_dir`, or `None` if no chain has been saved yet.
+pub fn load_chain(data_dir: &Path, rules: &ValidationRules, now: Time) -> Result<Option<Chain>, StorageError> {
+    let path = data_dir.join(CHAIN_FILENAME);
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == ErrorKind::NotFound => {
+            return Ok(None);
+        },
+        Err(error) => {
+            return Err(StorageError::ReadError(path, error));
+        },
+    };
+    
+    let chain: Chain = match serde_json::from_str(contents.as_str()) {
+        Ok(chain) => chain,
+        Err(error) => {
+            return Err(StorageError::ParseError(path, error));
+        },
+    };
+    
+    match chain.validate(rules, now) {
+        Ok(()) => Ok(Some(chain)),
+        Err(error) => Err(StorageError::InvalidChain(path, error)),
+    }
+}
+
+/// Saves `chain` to `data_dir`, creating the directory if needed.
+///
+/// The chain is written to a temporary file first, so an interrupted save leaves the previous chain intact.
+pub fn save_chain(data_dir: &Path, chain: &Chain) -> Result<(), StorageError> {
+    let path = data_dir.join(CHAIN_FILENAME);
+    let temporary_path = path.with_extension("json.tmp");
+    let contents = serde_json::to_string(chain).expect("Chain must serialize");
+    
+    fs::create_dir_all(data_dir)
+        .and_then(|_| fs::write(&temporary_path, contents))
+        .and_then(|_| fs::rename(&temporary_path, &path))
+        .map_err(|error| StorageError::WriteError(path, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use simple_blockchain::block::Block;
+    
+    use super::*;
+    
+    fn data_dir(name: &str) -> PathBuf {
+        let data_dir = std::env::temp_dir().join(format!("blockchain-storage-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&data_dir);
+        data_dir
+    }
+    
+    fn now() -> Time {
+        Time::from_unix_millis(1_600_000_000_000)
+    }
+    
+    #[test]
+    fn saved_chain_is_loaded() {
+        let data_dir = data_dir("saved");
+        let chain = Chain::new(&Block::genesis(now()));
+        
+        assert!(load_chain(&data_dir, &ValidationRules::default(), now()).unwrap().is_none());
+        
+        save_chain(&data_dir, &chain).unwrap();
+        let loaded_chain = load_chain(&data_dir, &ValidationRules::default(), now()).unwrap().unwrap();
+        
+        assert_eq!(loaded_chain.last().unwrap().hash(), chain.last().unwrap().hash());
+        
+        fs::remove_dir_all(&data_dir).unwrap();
+    }
+    
+    #[test]
+    fn corrupted_chain_is_rejected() {
+        let data_dir = data_dir("corrupted");
+        fs::create_dir_all(&data_dir).unwrap();
+        
+        fs::write(data_dir.join(CHAIN_FILENAME), "{\"chain\": [").unwrap();
+        
+        assert!(matches!(load_chain(&data_dir, &ValidationRules::default(), now()),
+            Err(StorageError::ParseError(_, _))));
+        
+        fs::write(data_dir.join(CHAIN_FILENAME), "{\"chain\": []}").unwrap();
+        
+        assert!(matches!(load_chain(&data_dir, &ValidationRules::default(), now()),
+            Err(StorageError::InvalidChain(_, ValidationError::EmptyChain))));
+        
+        fs::remove_dir_all(&data_dir).unwrap();
+    }
+}
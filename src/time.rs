@@ -1,32 +1,89 @@
+//! Timestamps of blocks.
+
 use std::result::Result;
 use std::fmt::{Display, Formatter, Error};
-use std::time::SystemTime;
-use chrono::{DateTime, offset::Utc};
-use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use chrono::{DateTime, SecondsFormat, offset::Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
 
-#[derive(Copy, Clone, Deserialize, Serialize)]
+/// A point in time with nanosecond precision.
+///
+/// Serialized as an RFC 3339 string in human-readable formats and as `SystemTime` otherwise. Deserialization
+/// also accepts Unix milliseconds and the legacy `{"system_time": ...}` object.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Time {
     system_time: SystemTime,
 }
 
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TimeRepresentation {
+    Rfc3339(String),
+    UnixMillis(i64),
+    SystemTime(SystemTime),
+    Legacy {
+        system_time: SystemTime,
+    },
+}
+
 impl Time {
-    #[allow(dead_code)]
+    /// Creates a time from a `SystemTime`.
     pub fn new(system_time: SystemTime) -> Time {
         Time {
             system_time,
         }
     }
     
+    /// Returns the current system time.
     pub fn now() -> Time {
         Time {
             system_time: SystemTime::now(),
         }
     }
     
-    #[allow(dead_code)]
+    /// Returns the underlying `SystemTime`.
     pub fn system_time(&self) -> SystemTime {
         self.system_time
     }
+    
+    /// Parses an RFC 3339 string, converting it to UTC.
+    pub fn from_rfc3339(string: &str) -> Result<Time, chrono::ParseError> {
+        let date_time = DateTime::parse_from_rfc3339(string)?;
+        Ok(Time::new(date_time.with_timezone(&Utc).into()))
+    }
+    
+    /// Creates a time from milliseconds since the Unix epoch; negative values lie before the epoch.
+    pub fn from_unix_millis(millis: i64) -> Time {
+        let duration = Duration::from_millis(millis.unsigned_abs());
+        
+        if millis < 0 {
+            Time::new(UNIX_EPOCH - duration)
+        } else {
+            Time::new(UNIX_EPOCH + duration)
+        }
+    }
+    
+    /// Formats the time as RFC 3339 in UTC with as many fractional digits as needed.
+    pub fn to_rfc3339(self) -> String {
+        let date_time: DateTime<Utc> = self.system_time.into();
+        date_time.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+    }
+    
+    /// Returns whole seconds since the Unix epoch and the nanoseconds within that second.
+    pub fn unix_time(&self) -> (i64, u32) {
+        match self.system_time.duration_since(UNIX_EPOCH) {
+            Ok(duration) => (duration.as_secs() as i64, duration.subsec_nanos()),
+            Err(error) => {
+                let duration = error.duration();
+                
+                if duration.subsec_nanos() == 0 {
+                    (-(duration.as_secs() as i64), 0)
+                } else {
+                    (-(duration.as_secs() as i64) - 1, 1_000_000_000 - duration.subsec_nanos())
+                }
+            },
+        }
+    }
 }
 
 impl Display for Time {
@@ -35,3 +92,77 @@ impl Display for Time {
         write!(fmt, "{}", date_time.format("%Y.%m.%d %H:%M:%S.%f"))
     }
 }
+
+impl Serialize for Time {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(self.to_rfc3339().as_str())
+        } else {
+            self.system_time.serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Time {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Time, D::Error> {
+        if !deserializer.is_human_readable() {
+            return SystemTime::deserialize(deserializer).map(Time::new);
+        }
+        
+        match TimeRepresentation::deserialize(deserializer)? {
+            TimeRepresentation::Rfc3339(string) => Time::from_rfc3339(string.as_str()).map_err(de::Error::custom),
+            TimeRepresentation::UnixMillis(millis) => Ok(Time::from_unix_millis(millis)),
+            TimeRepresentation::SystemTime(system_time) => Ok(Time::new(system_time)),
+            TimeRepresentation::Legacy { system_time } => Ok(Time::new(system_time)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // 2020-09-13T12:26:40.123456789Z
+    const SECONDS: u64 = 1_600_000_000;
+    const NANOSECONDS: u32 = 123_456_789;
+    
+    fn time() -> Time {
+        Time::new(UNIX_EPOCH + Duration::new(SECONDS, NANOSECONDS))
+    }
+    
+    #[test]
+    fn legacy_object_deserializes() {
+        let json = r#"{"system_time": {"secs_since_epoch": 1600000000, "nanos_since_epoch": 123456789}}"#;
+        
+        assert!(serde_json::from_str::<Time>(json).unwrap() == time());
+    }
+    
+    #[test]
+    fn rfc3339_deserializes() {
+        let json = r#""2020-09-13T14:26:40.123456789+02:00""#;
+        
+        assert!(serde_json::from_str::<Time>(json).unwrap() == time());
+    }
+    
+    #[test]
+    fn unix_millis_deserialize() {
+        let time = serde_json::from_str::<Time>("1600000000123").unwrap();
+        
+        assert_eq!(time.unix_time(), (SECONDS as i64, 123_000_000));
+    }
+    
+    #[test]
+    fn rfc3339_round_trips_nanoseconds() {
+        let json = serde_json::to_string(&time()).unwrap();
+        
+        assert_eq!(json, r#""2020-09-13T12:26:40.123456789Z""#);
+        assert!(serde_json::from_str::<Time>(json.as_str()).unwrap() == time());
+    }
+    
+    #[test]
+    fn binary_round_trips_nanoseconds() {
+        let bytes = bincode::serialize(&time()).unwrap();
+        
+        assert!(bincode::deserialize::<Time>(&bytes).unwrap() == time());
+    }
+}
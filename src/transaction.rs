@@ -1,6 +1,28 @@
-use sha256::digest;
+//! Transactions.
+
+use std::fmt::{Display, Formatter, Error};
 use serde::{Deserialize, Serialize};
 
+use crate::{canonical, hash, reward};
+
+use hash::Hash;
+
+/// Error accepting a transaction.
+#[derive(Debug)]
+pub enum TransactionError {
+    ReservedSender,
+}
+
+impl Display for TransactionError {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        match self {
+            TransactionError::ReservedSender => write!(fmt, "Sender \"{}\" is reserved for mining rewards",
+                reward::SENDER_FOR_MINING),
+        }
+    }
+}
+
+/// Transfer of `amount` from `sender` to `recipient`.
 #[derive(Clone, Deserialize, Serialize)]
 pub struct Transaction {
     sender: String,
@@ -9,6 +31,7 @@ pub struct Transaction {
 }
 
 impl Transaction {
+    /// Creates a transaction.
     pub fn new(sender: &str, recipient: &str, amount: i64) -> Transaction {
         Transaction {
             sender: String::from(sender),
@@ -17,23 +40,33 @@ impl Transaction {
         }
     }
     
-    #[allow(dead_code)]
+    /// Creates the mining reward transaction paying `amount` to `recipient`.
+    pub fn reward(recipient: &str, amount: i64) -> Transaction {
+        Transaction::new(reward::SENDER_FOR_MINING, recipient, amount)
+    }
+    
+    /// Tells whether the transaction is a mining reward.
+    pub fn is_reward(&self) -> bool {
+        self.sender == reward::SENDER_FOR_MINING
+    }
+    
+    /// Returns the sender.
     pub fn sender(&self) -> &str {
         self.sender.as_str()
     }
     
-    #[allow(dead_code)]
+    /// Returns the recipient.
     pub fn recipient(&self) -> &str {
         self.recipient.as_str()
     }
     
-    #[allow(dead_code)]
+    /// Returns the transferred amount.
     pub fn amount(&self) -> i64 {
         self.amount
     }
     
-    pub fn hash(&self) -> String {
-        let string = format!("{}|{}|{}", self.sender, self.recipient, self.amount);
-        digest(string)
+    /// Returns the hash of the transaction's canonical encoding.
+    pub fn hash(&self) -> Hash {
+        Hash::digest(&canonical::encode_transaction(self))
     }
 }
@@ -1,3 +1,5 @@
+//! Lists of transactions.
+
 use std::slice::Iter;
 use serde::{Deserialize, Serialize};
 
@@ -5,27 +7,47 @@ use crate::transaction;
 
 use transaction::Transaction;
 
-#[derive(Clone, Deserialize, Serialize)]
+/// Ordered list of transactions of a block or of the mempool.
+#[derive(Clone, Default, Deserialize, Serialize)]
 pub struct Transactions {
     transactions: Vec<Transaction>,
 }
 
 impl Transactions {
+    /// Creates an empty list.
     pub fn new() -> Transactions {
         Transactions {
             transactions: Vec::new(),
         }
     }
     
+    /// Returns the number of transactions.
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+    
+    /// Tells whether the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+    
+    /// Returns the last transaction.
+    pub fn last(&self) -> Option<&Transaction> {
+        self.transactions.last()
+    }
+    
+    /// Iterates over the transactions in order.
     pub fn iter(&self) -> Iter<'_, Transaction> {
         self.transactions.iter()
     }
     
+    /// Appends a transaction and returns it.
     pub fn push(&mut self, transaction: Transaction) -> &Transaction {
         self.transactions.push(transaction);
         self.transactions.last().expect("Transactions must contain at least one item")
     }
     
+    /// Removes all transactions.
     pub fn clear(&mut self) {
         self.transactions.clear()
     }
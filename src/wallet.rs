@@ -0,0 +1,356 @@
+//! Keypairs, the addresses derived from them and signed transactions.
+//!
+//! An address is the first 20 bytes of the SHA-256 hash of an Ed25519 public key. A transaction is signed over
+//! its hash together with the network id and a nonce chosen by the sender, so a signature can't be replayed on
+//! another network. Whoever accepts signed transactions must still reject a nonce the sender has already used.
+//!
+//! ```
+//! use simple_blockchain::wallet::Keypair;
+//!
+//! let keypair = Keypair::generate();
+//! let signed_transaction = keypair.sign(&keypair.transaction("recipient", 5), "main", 1).unwrap();
+//!
+//! assert!(signed_transaction.verify("main").is_ok());
+//! assert!(signed_transaction.verify("test").is_err());
+//! ```
+
+use std::fmt::{Display, Formatter, Error};
+use std::str::FromStr;
+use ed25519_dalek::{Signer, SigningKey, Verifier, VerifyingKey, PUBLIC_KEY_LENGTH, SECRET_KEY_LENGTH,
+    SIGNATURE_LENGTH};
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use zeroize::Zeroizing;
+
+use crate::{hex_string, hash, address, transaction, canonical};
+
+use hex_string::HexError;
+
+use hash::Hash;
+use address::{Address, ADDRESS_LENGTH};
+use transaction::Transaction;
+
+/// Error handling keys or signatures.
+#[derive(Debug)]
+pub enum WalletError {
+    InvalidHex(hex::FromHexError),
+    InvalidLength(usize, usize),
+    InvalidPublicKey,
+    InvalidAmount(i64),
+    SenderMismatch(String, Address),
+    NetworkMismatch(String, String),
+    InvalidSignature,
+}
+
+impl Display for WalletError {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        match self {
+            WalletError::InvalidHex(error) => write!(fmt, "Invalid hex string: {}", error),
+            WalletError::InvalidLength(length, expected_length) => write!(fmt,
+                "Invalid length: {} bytes instead of {}", length, expected_length),
+            WalletError::InvalidPublicKey => write!(fmt, "Invalid public key"),
+            WalletError::InvalidAmount(amount) => write!(fmt, "Amount {} is not positive", amount),
+            WalletError::SenderMismatch(sender, address) => write!(fmt,
+                "Sender \"{}\" doesn\'t match the signing address {}", sender, address),
+            WalletError::NetworkMismatch(network_id, expected_network_id) => write!(fmt,
+                "Transaction is signed for network \"{}\" instead of \"{}\"", network_id, expected_network_id),
+            WalletError::InvalidSignature => write!(fmt, "Invalid signature"),
+        }
+    }
+}
+
+impl From<HexError> for WalletError {
+    fn from(error: HexError) -> WalletError {
+        match error {
+            HexError::InvalidHex(error) => WalletError::InvalidHex(error),
+            HexError::InvalidLength(length, expected_length) => WalletError::InvalidLength(length, expected_length),
+        }
+    }
+}
+
+/// Ed25519 public key, written as 64 hex digits.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct PublicKey(VerifyingKey);
+
+impl PublicKey {
+    /// Parses raw public key bytes.
+    pub fn from_bytes(bytes: &[u8; PUBLIC_KEY_LENGTH]) -> Result<PublicKey, WalletError> {
+        match VerifyingKey::from_bytes(bytes) {
+            Ok(verifying_key) => Ok(PublicKey(verifying_key)),
+            Err(_) => Err(WalletError::InvalidPublicKey),
+        }
+    }
+    
+    /// Returns the raw public key bytes.
+    pub fn as_bytes(&self) -> &[u8; PUBLIC_KEY_LENGTH] {
+        self.0.as_bytes()
+    }
+    
+    /// Derives the address of the key.
+    pub fn address(&self) -> Address {
+        let hash = Hash::digest(self.as_bytes());
+        let mut bytes = [0; ADDRESS_LENGTH];
+        bytes.copy_from_slice(&hash.as_bytes()[..ADDRESS_LENGTH]);
+        
+        Address::new(bytes)
+    }
+}
+
+impl Display for PublicKey {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        write!(fmt, "{}", hex::encode(self.as_bytes()))
+    }
+}
+
+impl FromStr for PublicKey {
+    type Err = WalletError;
+    
+    fn from_str(string: &str) -> Result<PublicKey, WalletError> {
+        PublicKey::from_bytes(&hex_string::decode_hex(string)?)
+    }
+}
+
+impl Serialize for PublicKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        hex_string::serialize_str(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PublicKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<PublicKey, D::Error> {
+        hex_string::deserialize_str(deserializer)
+    }
+}
+
+/// Ed25519 signature, written as 128 hex digits.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Signature(ed25519_dalek::Signature);
+
+impl Signature {
+    /// Returns the raw signature bytes.
+    pub fn to_bytes(&self) -> [u8; SIGNATURE_LENGTH] {
+        self.0.to_bytes()
+    }
+}
+
+impl Display for Signature {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        write!(fmt, "{}", hex::encode(self.to_bytes()))
+    }
+}
+
+impl FromStr for Signature {
+    type Err = WalletError;
+    
+    fn from_str(string: &str) -> Result<Signature, WalletError> {
+        Ok(Signature(ed25519_dalek::Signature::from_bytes(&hex_string::decode_hex(string)?)))
+    }
+}
+
+impl Serialize for Signature {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        hex_string::serialize_str(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Signature {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Signature, D::Error> {
+        hex_string::deserialize_str(deserializer)
+    }
+}
+
+/// Ed25519 keypair of an account; the secret key is wiped from memory when the keypair is dropped.
+pub struct Keypair(SigningKey);
+
+impl Keypair {
+    /// Generates a keypair from the operating system's random number generator.
+    pub fn generate() -> Keypair {
+        let mut secret_key = Zeroizing::new([0; SECRET_KEY_LENGTH]);
+        OsRng.fill_bytes(secret_key.as_mut());
+        
+        Keypair::from_secret_bytes(&secret_key)
+    }
+    
+    /// Restores a keypair from its secret key.
+    pub fn from_secret_bytes(secret_key: &[u8; SECRET_KEY_LENGTH]) -> Keypair {
+        Keypair(SigningKey::from_bytes(secret_key))
+    }
+    
+    /// Returns the secret key.
+    pub fn secret_bytes(&self) -> Zeroizing<[u8; SECRET_KEY_LENGTH]> {
+        Zeroizing::new(self.0.to_bytes())
+    }
+    
+    /// Returns the public key.
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey(self.0.verifying_key())
+    }
+    
+    /// Returns the address of the account.
+    pub fn address(&self) -> Address {
+        self.public_key().address()
+    }
+    
+    /// Creates a transaction of `amount` from the account to `recipient`.
+    pub fn transaction(&self, recipient: &str, amount: i64) -> Transaction {
+        Transaction::new(self.address().to_string().as_str(), recipient, amount)
+    }
+    
+    /// Signs a transaction for the network `network_id` with the sender's `nonce`; its sender must be the
+    /// account's address and its amount positive.
+    pub fn sign(&self, transaction: &Transaction, network_id: &str, nonce: u64) ->
+        Result<SignedTransaction, WalletError> {
+        let address = self.address();
+        
+        if transaction.sender() != address.to_string() {
+            return Err(WalletError::SenderMismatch(String::from(transaction.sender()), address));
+        }
+        
+        if transaction.amount() <= 0 {
+            return Err(WalletError::InvalidAmount(transaction.amount()));
+        }
+        
+        let message = canonical::encode_signing_message(network_id, nonce, transaction);
+        
+        Ok(SignedTransaction {
+            transaction: transaction.clone(),
+            network_id: String::from(network_id),
+            nonce,
+            public_key: self.public_key(),
+            signature: Signature(self.0.sign(&message)),
+        })
+    }
+}
+
+/// Transaction together with the network and nonce it is signed for, the public key of its sender and the
+/// sender's signature.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct SignedTransaction {
+    transaction: Transaction,
+    network_id: String,
+    nonce: u64,
+    public_key: PublicKey,
+    signature: Signature,
+}
+
+impl SignedTransaction {
+    /// Returns the signed transaction.
+    pub fn transaction(&self) -> &Transaction {
+        &self.transaction
+    }
+    
+    /// Returns the id of the network the transaction is signed for.
+    pub fn network_id(&self) -> &str {
+        self.network_id.as_str()
+    }
+    
+    /// Returns the sender's nonce.
+    pub fn nonce(&self) -> u64 {
+        self.nonce
+    }
+    
+    /// Returns the public key of the sender.
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+    
+    /// Returns the signature.
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+    
+    /// Checks that the transaction is signed for the network `network_id`, that the public key belongs to the
+    /// sender and that the signature is valid.
+    pub fn verify(&self, network_id: &str) -> Result<(), WalletError> {
+        if self.network_id != network_id {
+            return Err(WalletError::NetworkMismatch(self.network_id.clone(), String::from(network_id)));
+        }
+        
+        let address = self.public_key.address();
+        
+        if self.transaction.sender() != address.to_string() {
+            return Err(WalletError::SenderMismatch(String::from(self.transaction.sender()), address));
+        }
+        
+        let message = canonical::encode_signing_message(self.network_id.as_str(), self.nonce, &self.transaction);
+        
+        match self.public_key.0.verify(&message, &self.signature.0) {
+            Ok(()) => Ok(()),
+            Err(_) => Err(WalletError::InvalidSignature),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures;
+    
+    use super::*;
+    
+    fn signed_transaction() -> (Keypair, SignedTransaction) {
+        let keypair = Keypair::generate();
+        let signed_transaction = keypair.sign(&keypair.transaction("recipient", 5), "main", 1).unwrap();
+        
+        (keypair, signed_transaction)
+    }
+    
+    fn tampered(signed_transaction: &SignedTransaction, field: &str, value: serde_json::Value) -> SignedTransaction {
+        fixtures::patched(signed_transaction, field, Some(value))
+    }
+    
+    #[test]
+    fn signed_transaction_verifies() {
+        let (_, signed_transaction) = signed_transaction();
+        let json = serde_json::to_string(&signed_transaction).unwrap();
+        
+        assert!(serde_json::from_str::<SignedTransaction>(json.as_str()).unwrap().verify("main").is_ok());
+    }
+    
+    #[test]
+    fn signature_is_bound_to_network() {
+        let (_, signed_transaction) = signed_transaction();
+        
+        assert!(matches!(signed_transaction.verify("test"), Err(WalletError::NetworkMismatch(_, _))));
+        assert!(matches!(tampered(&signed_transaction, "network_id", "test".into()).verify("test"),
+            Err(WalletError::InvalidSignature)));
+    }
+    
+    #[test]
+    fn signature_is_bound_to_nonce() {
+        let (_, signed_transaction) = signed_transaction();
+        
+        assert!(matches!(tampered(&signed_transaction, "nonce", 2.into()).verify("main"),
+            Err(WalletError::InvalidSignature)));
+    }
+    
+    #[test]
+    fn signature_is_bound_to_transaction() {
+        let (keypair, signed_transaction) = signed_transaction();
+        let transaction = serde_json::to_value(keypair.transaction("recipient", 6)).unwrap();
+        
+        assert!(matches!(tampered(&signed_transaction, "transaction", transaction).verify("main"),
+            Err(WalletError::InvalidSignature)));
+    }
+    
+    #[test]
+    fn sender_must_own_the_key() {
+        let (_, signed_transaction) = signed_transaction();
+        let other_keypair = Keypair::generate();
+        let transaction = serde_json::to_value(other_keypair.transaction("recipient", 5)).unwrap();
+        
+        assert!(matches!(other_keypair.sign(&Keypair::generate().transaction("recipient", 5), "main", 1),
+            Err(WalletError::SenderMismatch(_, _))));
+        assert!(matches!(tampered(&signed_transaction, "transaction", transaction).verify("main"),
+            Err(WalletError::SenderMismatch(_, _))));
+    }
+    
+    #[test]
+    fn amount_must_be_positive() {
+        let keypair = Keypair::generate();
+        
+        for amount in [0, -5] {
+            assert!(matches!(keypair.sign(&keypair.transaction("recipient", amount), "main", 1),
+                Err(WalletError::InvalidAmount(invalid_amount)) if invalid_amount == amount));
+        }
+    }
+}